@@ -1,23 +1,54 @@
-use bevy::prelude::*;
+use bevy::{
+    ecs::{query::WorldQuery, system::SystemParam},
+    prelude::*,
+};
+use bevy_asset_loader::prelude::*;
+use bevy_kira_audio::{Audio, AudioControl, AudioSource};
 use bevy_rapier3d::{prelude::*, rapier::geometry::CollisionEventFlags};
 
+use serde::Deserialize;
+
 use crate::{weapons::Projectile, GlobalState};
 
 pub struct DamagePlugin;
 
 impl Plugin for DamagePlugin {
     fn build(&self, app: &mut App) {
+        app.add_collection_to_loading_state::<_, DamageAssets>(GlobalState::AssetLoading);
+
         app.add_event::<DamageEvent>();
         app.add_event::<KillEvent>();
+        app.add_event::<ExplosionEvent>();
+        app.add_event::<ShieldBreakEvent>();
+        app.add_event::<ArmorBreakEvent>();
 
-        app.add_systems(Update, apply_damage.run_if(in_state(GlobalState::InGame)));
+        app.add_systems(
+            Update,
+            (
+                apply_damage,
+                apply_explosions,
+                recharge_shields,
+                play_shield_break_sound,
+            )
+                .run_if(in_state(GlobalState::InGame)),
+        );
     }
 }
 
+#[derive(AssetCollection, Resource)]
+pub struct DamageAssets {
+    #[asset(path = "sounds/shield_break.wav")]
+    pub shield_break_sound: Handle<AudioSource>,
+}
+
 #[derive(Clone, Copy, Event)]
 pub struct DamageEvent {
     pub entity: Entity,
     pub direction: Vec3,
+    // Damage that got through shield and armor to the health
+    pub amount: i32,
+    // Weapon that fired the projectile
+    pub weapon: Option<Entity>,
 }
 
 #[derive(Clone, Copy, Event)]
@@ -25,9 +56,46 @@ pub struct KillEvent {
     pub entity: Entity,
 }
 
+// Sent when shield of the entity drops to 0
+#[derive(Clone, Copy, Event)]
+pub struct ShieldBreakEvent {
+    pub entity: Entity,
+}
+
+// Sent when armor of the entity is used up
+#[derive(Clone, Copy, Event)]
+pub struct ArmorBreakEvent {
+    pub entity: Entity,
+}
+
+// Damages everything in the `radius` except the `source`.
+// Damage linearly falls off to 0 at the edge.
+#[derive(Clone, Copy, Event)]
+pub struct ExplosionEvent {
+    pub source: Entity,
+    pub translation: Vec3,
+    pub radius: f32,
+    pub damage: i32,
+    pub kind: DamageKind,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DamageKind {
+    // Pistol and minigun rounds
+    #[default]
+    Ballistic,
+    // Shotgun pellets
+    Pellet,
+    // Thrown weapons
+    Blunt,
+    Explosive,
+    Fire,
+}
+
 #[derive(Default, Component)]
 pub struct Damage {
     pub damage: i32,
+    pub kind: DamageKind,
 }
 
 #[derive(Default, Component)]
@@ -35,14 +103,157 @@ pub struct Health {
     pub health: i32,
 }
 
+// Takes all damage before armor and health. Recharges
+// after some time without hits.
+#[derive(Component)]
+pub struct Shield {
+    pub shield: i32,
+    pub max_shield: i32,
+    // Shield points per second
+    recharge_rate: f32,
+    recharge_delay_timer: Timer,
+    // Part of the shield point recharged so far
+    recharge: f32,
+}
+
+impl Shield {
+    pub fn new(max_shield: i32, recharge_delay: f32, recharge_rate: f32) -> Self {
+        Self {
+            shield: max_shield,
+            max_shield,
+            recharge_rate,
+            recharge_delay_timer: Timer::from_seconds(recharge_delay, TimerMode::Once),
+            recharge: 0.0,
+        }
+    }
+}
+
+// Absorbs `absorb` fraction of the damage that got
+// through the shield until `armor` is used up
+#[derive(Component)]
+pub struct Armor {
+    pub armor: i32,
+    pub absorb: f32,
+}
+
+// Entity that takes damage from projectiles and explosions
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+struct DamageTarget {
+    entity: Entity,
+    health: &'static mut Health,
+    resistances: Option<&'static Resistances>,
+    shield: Option<&'static mut Shield>,
+    armor: Option<&'static mut Armor>,
+}
+
+// Events sent when damage is applied
+#[derive(SystemParam)]
+struct DamageEvents<'w> {
+    kill: EventWriter<'w, KillEvent>,
+    damage: EventWriter<'w, DamageEvent>,
+    shield_break: EventWriter<'w, ShieldBreakEvent>,
+    armor_break: EventWriter<'w, ArmorBreakEvent>,
+}
+
+// Routes damage through shield and armor. Returns
+// damage left for the health.
+fn absorb_damage(
+    entity: Entity,
+    mut damage: i32,
+    shield: Option<Mut<Shield>>,
+    armor: Option<Mut<Armor>>,
+    events: &mut DamageEvents,
+) -> i32 {
+    if let Some(mut shield) = shield {
+        // every hit delays the recharge, even when shield is down
+        shield.recharge_delay_timer.reset();
+        shield.recharge = 0.0;
+        if 0 < shield.shield {
+            let absorbed = damage.min(shield.shield);
+            shield.shield -= absorbed;
+            damage -= absorbed;
+            if shield.shield == 0 {
+                events.shield_break.send(ShieldBreakEvent { entity });
+            }
+        }
+    }
+
+    if let Some(mut armor) = armor {
+        if 0 < armor.armor && 0 < damage {
+            let absorbed = ((damage as f32 * armor.absorb) as i32).min(armor.armor);
+            armor.armor -= absorbed;
+            damage -= absorbed;
+            if armor.armor == 0 {
+                events.armor_break.send(ArmorBreakEvent { entity });
+            }
+        }
+    }
+
+    damage
+}
+
+// Multipliers of the incoming damage of each kind. Values
+// below 1.0 resist the damage, values above 1.0 are weaknesses.
+// Entities without resistances take normal damage.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Component)]
+pub struct Resistances {
+    pub ballistic: f32,
+    pub pellet: f32,
+    pub blunt: f32,
+    pub explosive: f32,
+    pub fire: f32,
+}
+
+impl Default for Resistances {
+    fn default() -> Self {
+        Self {
+            ballistic: 1.0,
+            pellet: 1.0,
+            blunt: 1.0,
+            explosive: 1.0,
+            fire: 1.0,
+        }
+    }
+}
+
+impl Resistances {
+    pub fn multiplier(&self, kind: DamageKind) -> f32 {
+        match kind {
+            DamageKind::Ballistic => self.ballistic,
+            DamageKind::Pellet => self.pellet,
+            DamageKind::Blunt => self.blunt,
+            DamageKind::Explosive => self.explosive,
+            DamageKind::Fire => self.fire,
+        }
+    }
+
+    // Combines both multipliers of each kind
+    pub fn stack(&self, other: &Resistances) -> Self {
+        Self {
+            ballistic: self.ballistic * other.ballistic,
+            pellet: self.pellet * other.pellet,
+            blunt: self.blunt * other.blunt,
+            explosive: self.explosive * other.explosive,
+            fire: self.fire * other.fire,
+        }
+    }
+
+    fn apply(resistances: Option<&Resistances>, damage: i32, kind: DamageKind) -> i32 {
+        match resistances {
+            Some(resistances) => (damage as f32 * resistances.multiplier(kind)) as i32,
+            None => damage,
+        }
+    }
+}
+
 fn apply_damage(
     projectiles: Query<&Projectile>,
     damage_objects: Query<(Entity, &Damage)>,
     mut commands: Commands,
-    mut kill_events: EventWriter<KillEvent>,
-    mut damage_events: EventWriter<DamageEvent>,
+    mut events: DamageEvents,
     mut collision_events: EventReader<CollisionEvent>,
-    mut entities: Query<(Entity, &mut Health)>,
+    mut entities: Query<DamageTarget>,
 ) {
     for collision_event in collision_events.read() {
         let (collider_1, collider_2, flags) = match collision_event {
@@ -53,50 +264,132 @@ fn apply_damage(
             return;
         }
 
-        let ((damage_entity, damage), (entity, mut entity_health)) =
-            if let Ok(p) = damage_objects.get(*collider_1) {
-                let e = if let Ok(e) = entities.get_mut(*collider_2) {
-                    e
-                } else {
-                    continue;
-                };
-                (p, e)
-            } else if let Ok(p) = damage_objects.get(*collider_2) {
-                let e = if let Ok(e) = entities.get_mut(*collider_1) {
-                    e
-                } else {
-                    continue;
-                };
-                (p, e)
+        let ((damage_entity, damage), mut target) = if let Ok(p) = damage_objects.get(*collider_1) {
+            let e = if let Ok(e) = entities.get_mut(*collider_2) {
+                e
+            } else {
+                continue;
+            };
+            (p, e)
+        } else if let Ok(p) = damage_objects.get(*collider_2) {
+            let e = if let Ok(e) = entities.get_mut(*collider_1) {
+                e
             } else {
                 continue;
             };
+            (p, e)
+        } else {
+            continue;
+        };
 
         // skip enemies that were killed by prevous iterations
-        if entity_health.health <= 0 {
+        if target.health.health <= 0 {
             continue;
         }
-        entity_health.health -= damage.damage;
+        let entity = target.entity;
+        let amount = absorb_damage(
+            entity,
+            Resistances::apply(target.resistances, damage.damage, damage.kind),
+            target.shield,
+            target.armor,
+            &mut events,
+        );
+        target.health.health -= amount;
 
         let Some(mut e) = commands.get_entity(damage_entity) else {
             continue;
         };
         e.remove::<Damage>();
 
-        if entity_health.health <= 0 {
+        if target.health.health <= 0 {
             let Some(mut e) = commands.get_entity(entity) else {
                 continue;
             };
             e.remove::<Health>();
-            kill_events.send(KillEvent { entity });
+            events.kill.send(KillEvent { entity });
         } else {
             let Ok(projectile) = projectiles.get(damage_entity) else {
                 continue;
             };
-            damage_events.send(DamageEvent {
+            events.damage.send(DamageEvent {
                 entity,
                 direction: projectile.direction,
+                amount,
+                weapon: projectile.weapon,
             });
         }
     }
 }
+
+fn apply_explosions(
+    mut commands: Commands,
+    mut events: DamageEvents,
+    mut explosion_events: EventReader<ExplosionEvent>,
+    mut entities: Query<(DamageTarget, &GlobalTransform)>,
+) {
+    for explosion in explosion_events.read() {
+        for (target, entity_transform) in entities.iter_mut() {
+            let entity = target.entity;
+            let mut entity_health = target.health;
+            if entity == explosion.source || entity_health.health <= 0 {
+                continue;
+            }
+
+            let v = entity_transform.translation() - explosion.translation;
+            let distance = v.length();
+            if explosion.radius <= distance {
+                continue;
+            }
+            let falloff = 1.0 - distance / explosion.radius;
+            let damage = Resistances::apply(
+                target.resistances,
+                (explosion.damage as f32 * falloff) as i32,
+                explosion.kind,
+            );
+            let damage = absorb_damage(entity, damage, target.shield, target.armor, &mut events);
+            entity_health.health -= damage;
+
+            if entity_health.health <= 0 {
+                let Some(mut e) = commands.get_entity(entity) else {
+                    continue;
+                };
+                e.remove::<Health>();
+                events.kill.send(KillEvent { entity });
+            } else {
+                events.damage.send(DamageEvent {
+                    entity,
+                    direction: v.normalize_or_zero(),
+                    amount: damage,
+                    weapon: None,
+                });
+            }
+        }
+    }
+}
+
+fn recharge_shields(time: Res<Time>, mut shields: Query<&mut Shield>) {
+    for mut shield in shields.iter_mut() {
+        if shield.max_shield <= shield.shield {
+            continue;
+        }
+        shield.recharge_delay_timer.tick(time.delta());
+        if !shield.recharge_delay_timer.finished() {
+            continue;
+        }
+        shield.recharge += shield.recharge_rate * time.delta_seconds();
+        let recharge = shield.recharge as i32;
+        shield.recharge -= recharge as f32;
+        shield.shield = (shield.shield + recharge).min(shield.max_shield);
+    }
+}
+
+fn play_shield_break_sound(
+    audio: Res<Audio>,
+    damage_assets: Res<DamageAssets>,
+    mut shield_break_events: EventReader<ShieldBreakEvent>,
+) {
+    // shields breaking in the same frame make one sound
+    if shield_break_events.read().count() != 0 {
+        audio.play(damage_assets.shield_break_sound.clone());
+    }
+}
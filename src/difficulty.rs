@@ -0,0 +1,309 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{enemies::EnemyType, level::LevelInfo, run::RunConfig};
+
+// If set, overrides default difficulty preset
+// selected in the main menu
+const DIFFICULTY_ENV: &str = "FRIDGES_DIFFICULTY";
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Difficulty::from_env());
+    }
+}
+
+// Past the end of the curve each full run length
+// adds this many enemies and enemy health
+const DIFFICULTY_ENDLESS_ENEMIES: f32 = 4.0;
+const DIFFICULTY_ENDLESS_HEALTH: f32 = 0.5;
+
+// One step of the difficulty curve. Step is used
+// from its `progress` until the next step starts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DifficultyStep {
+    // Fraction of the run from 0.0 to 1.0
+    pub progress: f32,
+    pub enemies: u32,
+    // Weights of enemies by their definition id, small '1',
+    // mid '2', big '3', kamikaze '4' and spawner '5'
+    pub enemy_mix: &'static [(EnemyType, u32)],
+    pub enemy_health: f32,
+    pub enemy_speed: f32,
+    pub enemy_fire_rate: f32,
+    // Divides aim spread and reaction time of enemies
+    pub enemy_accuracy: f32,
+    // Multiplier for the ammo in weapons
+    pub ammo: f32,
+    // Weights of pistol, shotgun and minigun spawns
+    pub weapon_mix: [u32; 3],
+    // Multiplier for the weights of enemy drops
+    pub drops: f32,
+}
+
+const DIFFICULTY_CURVE: [DifficultyStep; 6] = [
+    DifficultyStep {
+        progress: 0.0,
+        enemies: 3,
+        enemy_mix: &[(EnemyType('1'), 3), (EnemyType('2'), 1)],
+        enemy_health: 1.0,
+        enemy_speed: 1.0,
+        enemy_fire_rate: 1.0,
+        enemy_accuracy: 1.0,
+        ammo: 1.0,
+        weapon_mix: [2, 2, 1],
+        drops: 1.0,
+    },
+    DifficultyStep {
+        progress: 0.2,
+        enemies: 4,
+        enemy_mix: &[
+            (EnemyType('1'), 2),
+            (EnemyType('2'), 2),
+            (EnemyType('4'), 1),
+        ],
+        enemy_health: 1.0,
+        enemy_speed: 1.0,
+        enemy_fire_rate: 1.05,
+        enemy_accuracy: 1.05,
+        ammo: 1.0,
+        weapon_mix: [1, 3, 1],
+        drops: 1.0,
+    },
+    DifficultyStep {
+        progress: 0.4,
+        enemies: 5,
+        enemy_mix: &[
+            (EnemyType('1'), 2),
+            (EnemyType('2'), 3),
+            (EnemyType('4'), 1),
+        ],
+        enemy_health: 1.1,
+        enemy_speed: 1.05,
+        enemy_fire_rate: 1.1,
+        enemy_accuracy: 1.1,
+        ammo: 0.95,
+        weapon_mix: [1, 3, 2],
+        drops: 0.95,
+    },
+    DifficultyStep {
+        progress: 0.6,
+        enemies: 6,
+        enemy_mix: &[
+            (EnemyType('1'), 2),
+            (EnemyType('2'), 3),
+            (EnemyType('3'), 1),
+            (EnemyType('4'), 2),
+            (EnemyType('5'), 1),
+        ],
+        enemy_health: 1.2,
+        enemy_speed: 1.1,
+        enemy_fire_rate: 1.15,
+        enemy_accuracy: 1.15,
+        ammo: 0.9,
+        weapon_mix: [0, 3, 3],
+        drops: 0.9,
+    },
+    DifficultyStep {
+        progress: 0.8,
+        enemies: 7,
+        enemy_mix: &[
+            (EnemyType('1'), 1),
+            (EnemyType('2'), 3),
+            (EnemyType('3'), 2),
+            (EnemyType('4'), 2),
+            (EnemyType('5'), 1),
+        ],
+        enemy_health: 1.35,
+        enemy_speed: 1.15,
+        enemy_fire_rate: 1.2,
+        enemy_accuracy: 1.2,
+        ammo: 0.85,
+        weapon_mix: [0, 2, 4],
+        drops: 0.85,
+    },
+    DifficultyStep {
+        progress: 1.0,
+        enemies: 8,
+        enemy_mix: &[
+            (EnemyType('1'), 1),
+            (EnemyType('2'), 3),
+            (EnemyType('3'), 3),
+            (EnemyType('4'), 3),
+            (EnemyType('5'), 1),
+        ],
+        enemy_health: 1.5,
+        enemy_speed: 1.2,
+        enemy_fire_rate: 1.3,
+        enemy_accuracy: 1.3,
+        ammo: 0.8,
+        weapon_mix: [0, 1, 5],
+        drops: 0.8,
+    },
+];
+
+// Steps of the curve ordered by progress
+pub fn difficulty_curve() -> &'static [DifficultyStep] {
+    &DIFFICULTY_CURVE
+}
+
+// Multipliers applied on top of the curve
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DifficultyModifiers {
+    pub player_health: f32,
+    pub enemy_health: f32,
+    pub enemy_speed: f32,
+    pub enemy_fire_rate: f32,
+    pub enemy_accuracy: f32,
+    pub ammo: f32,
+    pub drops: f32,
+}
+
+// Difficulty of the current room of the run
+#[derive(SystemParam)]
+pub struct RoomDifficulty<'w> {
+    pub difficulty: Res<'w, Difficulty>,
+    run_config: Res<'w, RunConfig>,
+    level_info: Res<'w, LevelInfo>,
+}
+
+impl RoomDifficulty<'_> {
+    pub fn step(&self) -> DifficultyStep {
+        self.difficulty
+            .step(self.run_config.progress(self.level_info.room))
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Nightmare,
+}
+
+impl Difficulty {
+    fn from_env() -> Self {
+        let Ok(value) = std::env::var(DIFFICULTY_ENV) else {
+            return Self::default();
+        };
+        match value.to_lowercase().as_str() {
+            "easy" => Difficulty::Easy,
+            "normal" => Difficulty::Normal,
+            "hard" => Difficulty::Hard,
+            "nightmare" => Difficulty::Nightmare,
+            _ => {
+                warn!("Invalid {DIFFICULTY_ENV} value: {value}. Using default difficulty.");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Nightmare,
+            Difficulty::Nightmare => Difficulty::Easy,
+        }
+    }
+
+    pub fn modifiers(self) -> DifficultyModifiers {
+        match self {
+            Difficulty::Easy => DifficultyModifiers {
+                player_health: 1.5,
+                enemy_health: 0.75,
+                enemy_speed: 0.85,
+                enemy_fire_rate: 0.75,
+                enemy_accuracy: 0.7,
+                ammo: 1.5,
+                drops: 1.3,
+            },
+            Difficulty::Normal => DifficultyModifiers {
+                player_health: 1.0,
+                enemy_health: 1.0,
+                enemy_speed: 1.0,
+                enemy_fire_rate: 1.0,
+                enemy_accuracy: 1.0,
+                ammo: 1.0,
+                drops: 1.0,
+            },
+            Difficulty::Hard => DifficultyModifiers {
+                player_health: 0.8,
+                enemy_health: 1.25,
+                enemy_speed: 1.15,
+                enemy_fire_rate: 1.25,
+                enemy_accuracy: 1.2,
+                ammo: 0.8,
+                drops: 0.85,
+            },
+            Difficulty::Nightmare => DifficultyModifiers {
+                player_health: 0.6,
+                enemy_health: 1.5,
+                enemy_speed: 1.3,
+                enemy_fire_rate: 1.5,
+                enemy_accuracy: 1.4,
+                ammo: 0.6,
+                drops: 0.7,
+            },
+        }
+    }
+
+    // Harder presets start further along the curve
+    fn progress_offset(self) -> f32 {
+        match self {
+            Difficulty::Easy => -0.2,
+            Difficulty::Normal => 0.0,
+            Difficulty::Hard => 0.2,
+            Difficulty::Nightmare => 0.4,
+        }
+    }
+
+    // Returns difficulty for the level at the `progress`
+    // fraction of the run.
+    pub fn step(self, progress: f32) -> DifficultyStep {
+        // endless runs go past the end of the curve
+        let overflow = (progress - 1.0).max(0.0);
+
+        let progress = progress + self.progress_offset();
+        let mut step = DIFFICULTY_CURVE
+            .iter()
+            .rev()
+            .find(|step| step.progress <= progress)
+            .copied()
+            .unwrap_or(DIFFICULTY_CURVE[0]);
+
+        step.enemies += (overflow * DIFFICULTY_ENDLESS_ENEMIES) as u32;
+        step.enemy_health *= 1.0 + overflow * DIFFICULTY_ENDLESS_HEALTH;
+
+        let modifiers = self.modifiers();
+        step.enemy_health *= modifiers.enemy_health;
+        step.enemy_speed *= modifiers.enemy_speed;
+        step.enemy_fire_rate *= modifiers.enemy_fire_rate;
+        step.enemy_accuracy *= modifiers.enemy_accuracy;
+        step.ammo *= modifiers.ammo;
+        step.drops *= modifiers.drops;
+        step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_step_spawns_enemies() {
+        for step in difficulty_curve() {
+            assert!(
+                step.enemy_mix.iter().any(|(_, weight)| *weight != 0),
+                "step at {} progress has no enemies",
+                step.progress
+            );
+            for (enemy_type, _) in step.enemy_mix {
+                assert_eq!(EnemyType::from_char(enemy_type.0), Some(*enemy_type));
+            }
+        }
+    }
+}
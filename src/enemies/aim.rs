@@ -0,0 +1,206 @@
+use bevy::{ecs::query::WorldQuery, prelude::*};
+use rand::Rng;
+
+use crate::{
+    player::{Player, PlayerVelocity},
+    run::CombatRng,
+    weapons::{ShootEvent, WeaponAttackTimer},
+};
+
+use super::{
+    boss::Boss, brain::EnemyBrain, definition::EnemyAimDefinition, hit::EnemyHitReaction,
+    perception::EnemyPerception, squad::SquadMember, Enemy, EnemyWeapon,
+};
+
+// Enemies do not shoot if the weapon points further
+// away than this from the aim direction
+const AIM_MAX_ANGLE: f32 = std::f32::consts::FRAC_PI_6;
+
+#[derive(Component)]
+pub struct EnemyAim {
+    // Max angle in radians shots deviate from the aim
+    spread: f32,
+    // Fraction of the player movement enemy accounts for
+    lead: f32,
+    // Delay between seeing the player and the first shot
+    reaction_timer: Timer,
+    burst: u32,
+    burst_shots: u32,
+    burst_pause_timer: Timer,
+}
+
+// Enemies without a weapon never shoot
+impl Default for EnemyAim {
+    fn default() -> Self {
+        Self::new(
+            &EnemyAimDefinition {
+                spread: 0.0,
+                reaction_time: 0.0,
+                burst: 1,
+                burst_pause: 0.0,
+                lead: 0.0,
+            },
+            1.0,
+        )
+    }
+}
+
+impl EnemyAim {
+    pub fn new(aim: &EnemyAimDefinition, accuracy: f32) -> Self {
+        let mut burst_pause_timer = Timer::from_seconds(aim.burst_pause, TimerMode::Once);
+        // first burst starts without a pause
+        burst_pause_timer.tick(burst_pause_timer.duration());
+        Self {
+            spread: aim.spread / accuracy,
+            lead: aim.lead,
+            reaction_timer: Timer::from_seconds(aim.reaction_time / accuracy, TimerMode::Once),
+            burst: aim.burst,
+            burst_shots: 0,
+            burst_pause_timer,
+        }
+    }
+}
+
+// Point to shoot at so projectile with `projectile_velocity`
+// meets a target moving with constant velocity. Returns current
+// target position if projectile can not catch the target.
+fn lead_target(
+    shooter: Vec3,
+    target: Vec3,
+    target_velocity: Vec3,
+    projectile_velocity: f32,
+) -> Vec3 {
+    let to_target = target - shooter;
+    // solve |to_target + target_velocity * t| = projectile_velocity * t
+    let a = target_velocity.length_squared() - projectile_velocity * projectile_velocity;
+    let b = 2.0 * to_target.dot(target_velocity);
+    let c = to_target.length_squared();
+
+    let time = if a.abs() < f32::EPSILON {
+        if b.abs() < f32::EPSILON {
+            return target;
+        }
+        -c / b
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return target;
+        }
+        let sqrt = discriminant.sqrt();
+        let t1 = (-b - sqrt) / (2.0 * a);
+        let t2 = (-b + sqrt) / (2.0 * a);
+        match (0.0 < t1, 0.0 < t2) {
+            (true, true) => t1.min(t2),
+            (true, false) => t1,
+            (false, true) => t2,
+            (false, false) => return target,
+        }
+    };
+    if time < 0.0 {
+        return target;
+    }
+    target + target_velocity * time
+}
+
+// Enemy that decides when to shoot its weapon
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+pub struct ShooterQuery {
+    enemy: &'static Enemy,
+    brain: &'static EnemyBrain,
+    perception: &'static EnemyPerception,
+    hit_reaction: &'static EnemyHitReaction,
+    squad_member: Option<&'static SquadMember>,
+    aim: &'static mut EnemyAim,
+}
+
+pub fn enemy_shoot(
+    time: Res<Time>,
+    player: Query<(&Transform, &PlayerVelocity), With<Player>>,
+    mut combat_rng: ResMut<CombatRng>,
+    mut enemies: Query<ShooterQuery, Without<Boss>>,
+    mut enemy_weapons: Query<
+        (Entity, &Parent, &GlobalTransform, &mut WeaponAttackTimer),
+        With<EnemyWeapon>,
+    >,
+    mut shoot_event: EventWriter<ShootEvent>,
+) {
+    let Ok((player_transform, player_velocity)) = player.get_single() else {
+        return;
+    };
+
+    for (weapon_entity, weapon_parent, weapon_global_transform, mut weapon_attack_timer) in
+        enemy_weapons.iter_mut()
+    {
+        let Ok(ShooterQueryItem {
+            enemy,
+            brain: enemy_brain,
+            perception: enemy_perception,
+            hit_reaction: enemy_hit_reaction,
+            squad_member,
+            aim: mut enemy_aim,
+        }) = enemies.get_mut(weapon_parent.get())
+        else {
+            continue;
+        };
+
+        // only enemies that fight and see the player shoot
+        if !enemy_brain.state.is_engaged() || !enemy_perception.sees_player {
+            enemy_aim.reaction_timer.reset();
+            enemy_aim.burst_shots = 0;
+            continue;
+        }
+
+        // hits interrupt the burst
+        if enemy_hit_reaction.is_flinching() {
+            enemy_aim.burst_shots = 0;
+            continue;
+        }
+
+        // squad mates are in the line of fire
+        if squad_member.is_some_and(|member| member.lane_blocked) {
+            continue;
+        }
+
+        enemy_aim.reaction_timer.tick(time.delta());
+        enemy_aim.burst_pause_timer.tick(time.delta());
+        if !enemy_aim.reaction_timer.finished()
+            || !enemy_aim.burst_pause_timer.finished()
+            || !weapon_attack_timer.ready
+        {
+            continue;
+        }
+
+        let Some(weapon_type) = enemy.weapon_type else {
+            continue;
+        };
+        let weapon_translation = weapon_global_transform.translation();
+        let target = lead_target(
+            weapon_translation,
+            player_transform.translation,
+            player_velocity.velocity * enemy_aim.lead,
+            weapon_type.projectile_velocity(),
+        );
+        let aim_direction = (target - weapon_translation).normalize();
+        if AIM_MAX_ANGLE < aim_direction.angle_between(weapon_global_transform.up()) {
+            continue;
+        }
+
+        let spread = combat_rng.0.gen_range(-1.0..=1.0) * enemy_aim.spread;
+        let direction = Quat::from_rotation_z(spread) * aim_direction;
+
+        weapon_attack_timer.attack_timer.reset();
+        weapon_attack_timer.ready = false;
+        shoot_event.send(ShootEvent {
+            weapon_entity,
+            weapon_translation,
+            direction,
+        });
+
+        enemy_aim.burst_shots += 1;
+        if enemy_aim.burst <= enemy_aim.burst_shots {
+            enemy_aim.burst_shots = 0;
+            enemy_aim.burst_pause_timer.reset();
+        }
+    }
+}
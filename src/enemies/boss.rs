@@ -0,0 +1,337 @@
+use bevy::{ecs::query::WorldQuery, prelude::*};
+use rand::Rng;
+
+use crate::{
+    damage::{DamageKind, ExplosionEvent, Health},
+    difficulty::DifficultyStep,
+    level::LevelObject,
+    player::Player,
+    run::CombatRng,
+    weapons::{ShootEvent, WeaponAssets, WeaponAttackTimer},
+};
+
+use super::{
+    brain::EnemyBrain, hit::EnemyHitReaction, perception::EnemyPerception, spawn_enemy,
+    DisabledEnemy, Enemy, EnemyDefinitions, EnemyResources, EnemyType,
+};
+
+// Boss switches to the next phase when its health
+// drops below these fractions
+const BOSS_VOLLEYS_HEALTH: f32 = 0.75;
+const BOSS_SUMMON_HEALTH: f32 = 0.5;
+const BOSS_HAZARDS_HEALTH: f32 = 0.25;
+
+// Boss does not shoot behind itself
+const BOSS_MAX_ANGLE: f32 = std::f32::consts::FRAC_PI_2;
+
+// Minigun sweeps from side to side around the player
+const BOSS_SWEEP_ANGLE: f32 = std::f32::consts::FRAC_PI_8;
+const BOSS_SWEEP_SPEED: f32 = 2.0;
+
+const BOSS_VOLLEY_TIME: f32 = 1.5;
+const BOSS_VOLLEY_SHOTS: u32 = 5;
+const BOSS_VOLLEY_ANGLE: f32 = std::f32::consts::FRAC_PI_4;
+
+const BOSS_SUMMON_TIME: f32 = 5.0;
+const BOSS_MAX_MINIONS: usize = 6;
+const BOSS_SUMMON_DISTANCE: f32 = 8.0;
+
+const BOSS_HAZARD_TIME: f32 = 2.0;
+// Time between a hazard mark appearing and the explosion
+const BOSS_HAZARD_DELAY: f32 = 1.2;
+// Hazards appear at most this far from the player
+const BOSS_HAZARD_SPREAD: f32 = 10.0;
+const BOSS_HAZARD_RADIUS: f32 = 8.0;
+const BOSS_HAZARD_DAMAGE: i32 = 40;
+const BOSS_HAZARD_HEIGHT: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BossPhase {
+    // Minigun sweeps
+    Sweep,
+    // Shotgun volleys
+    Volleys,
+    // Minigun sweeps and minion summons
+    Summon,
+    // Shotgun volleys and arena hazards
+    Hazards,
+}
+
+impl BossPhase {
+    pub fn from_health(health_fraction: f32) -> Self {
+        if BOSS_VOLLEYS_HEALTH < health_fraction {
+            BossPhase::Sweep
+        } else if BOSS_SUMMON_HEALTH < health_fraction {
+            BossPhase::Volleys
+        } else if BOSS_HAZARDS_HEALTH < health_fraction {
+            BossPhase::Summon
+        } else {
+            BossPhase::Hazards
+        }
+    }
+}
+
+// Sent when boss enters a new phase
+#[derive(Clone, Copy, Event)]
+pub struct BossPhaseEvent {
+    pub phase: BossPhase,
+}
+
+#[derive(Component)]
+pub struct Boss {
+    pub phase: BossPhase,
+    pub max_health: i32,
+    // Minions are spawned with the same difficulty as the boss
+    difficulty: DifficultyStep,
+    // Minigun is the `attached_weapon` of the boss
+    shotgun: Entity,
+    minion: EnemyType,
+    sweep_time: f32,
+    volley_timer: Timer,
+    summon_timer: Timer,
+    hazard_timer: Timer,
+    minions: Vec<Entity>,
+}
+
+impl Boss {
+    pub fn new(
+        difficulty: DifficultyStep,
+        max_health: i32,
+        shotgun: Entity,
+        minion: EnemyType,
+    ) -> Self {
+        Self {
+            phase: BossPhase::Sweep,
+            max_health,
+            difficulty,
+            shotgun,
+            minion,
+            sweep_time: 0.0,
+            volley_timer: Timer::from_seconds(BOSS_VOLLEY_TIME, TimerMode::Repeating),
+            summon_timer: Timer::from_seconds(BOSS_SUMMON_TIME, TimerMode::Repeating),
+            hazard_timer: Timer::from_seconds(BOSS_HAZARD_TIME, TimerMode::Repeating),
+            minions: vec![],
+        }
+    }
+}
+
+// Marks a spot that explodes after a delay
+#[derive(Component)]
+pub struct BossHazard {
+    source: Entity,
+    explosion_translation: Vec3,
+    timer: Timer,
+}
+
+// Phases only go forward, so healing the boss
+// does not repeat them.
+pub fn boss_phase_update(
+    mut bosses: Query<(&Health, &mut Boss), Without<DisabledEnemy>>,
+    mut boss_phase_events: EventWriter<BossPhaseEvent>,
+) {
+    for (health, mut boss) in bosses.iter_mut() {
+        let phase = BossPhase::from_health(health.health as f32 / boss.max_health as f32);
+        if boss.phase < phase {
+            boss.phase = phase;
+            boss_phase_events.send(BossPhaseEvent { phase });
+        }
+    }
+}
+
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+pub struct BossQuery {
+    entity: Entity,
+    enemy: &'static Enemy,
+    transform: &'static Transform,
+    brain: &'static EnemyBrain,
+    perception: &'static EnemyPerception,
+    hit_reaction: &'static EnemyHitReaction,
+    boss: &'static mut Boss,
+}
+
+impl BossQueryItem<'_> {
+    fn can_attack(&self) -> bool {
+        self.brain.state.is_engaged() && !self.hit_reaction.is_flinching()
+    }
+}
+
+pub fn boss_shoot(
+    time: Res<Time>,
+    player: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    mut bosses: Query<BossQuery, Without<DisabledEnemy>>,
+    mut boss_weapons: Query<(&GlobalTransform, &mut WeaponAttackTimer)>,
+    mut shoot_event: EventWriter<ShootEvent>,
+) {
+    let Ok(player_transform) = player.get_single() else {
+        return;
+    };
+
+    for mut item in bosses.iter_mut() {
+        if !item.can_attack() || !item.perception.sees_player {
+            continue;
+        }
+
+        let boss_forward = item.transform.rotation * Vec3::Y;
+        let to_player = player_transform.translation - item.transform.translation;
+        let to_player_direction = to_player.truncate().normalize_or_zero().extend(0.0);
+        let boss = item.boss.as_mut();
+
+        // minigun sweeps
+        if matches!(boss.phase, BossPhase::Sweep | BossPhase::Summon) {
+            boss.sweep_time += time.delta_seconds();
+            let sweep = (boss.sweep_time * BOSS_SWEEP_SPEED).sin() * BOSS_SWEEP_ANGLE;
+            let direction = Quat::from_rotation_z(sweep) * to_player_direction;
+            if let Some(minigun) = item.enemy.attached_weapon {
+                if let Ok((minigun_transform, mut minigun_timer)) = boss_weapons.get_mut(minigun) {
+                    if minigun_timer.ready && direction.angle_between(boss_forward) < BOSS_MAX_ANGLE
+                    {
+                        minigun_timer.attack_timer.reset();
+                        minigun_timer.ready = false;
+                        shoot_event.send(ShootEvent {
+                            weapon_entity: minigun,
+                            weapon_translation: minigun_transform.translation(),
+                            direction,
+                        });
+                    }
+                }
+            }
+        }
+
+        // shotgun volleys spread around the player
+        if matches!(boss.phase, BossPhase::Volleys | BossPhase::Hazards) {
+            boss.volley_timer.tick(time.delta());
+            if boss.volley_timer.just_finished() {
+                let shotgun = boss.shotgun;
+                if let Ok((shotgun_transform, _)) = boss_weapons.get(shotgun) {
+                    for i in 0..BOSS_VOLLEY_SHOTS {
+                        let angle =
+                            BOSS_VOLLEY_ANGLE * (i as f32 / (BOSS_VOLLEY_SHOTS - 1) as f32 - 0.5);
+                        let direction = Quat::from_rotation_z(angle) * to_player_direction;
+                        if BOSS_MAX_ANGLE < direction.angle_between(boss_forward) {
+                            continue;
+                        }
+                        shoot_event.send(ShootEvent {
+                            weapon_entity: shotgun,
+                            weapon_translation: shotgun_transform.translation(),
+                            direction,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Minions come out around the boss
+pub fn boss_summon(
+    time: Res<Time>,
+    enemy_definitions: Res<EnemyDefinitions>,
+    weapon_assets: Res<WeaponAssets>,
+    enemies: Query<(), With<Enemy>>,
+    mut bosses: Query<BossQuery, Without<DisabledEnemy>>,
+    mut combat_rng: ResMut<CombatRng>,
+    mut commands: Commands,
+) {
+    for mut item in bosses.iter_mut() {
+        item.boss.minions.retain(|minion| enemies.contains(*minion));
+
+        if !item.can_attack() || item.boss.phase != BossPhase::Summon {
+            continue;
+        }
+
+        let boss_translation = item.transform.translation;
+        let boss = item.boss.as_mut();
+        boss.summon_timer.tick(time.delta());
+        if boss.summon_timer.just_finished() && boss.minions.len() < BOSS_MAX_MINIONS {
+            let angle = combat_rng.0.gen_range(0.0..std::f32::consts::TAU);
+            let offset = Vec2::from_angle(angle) * BOSS_SUMMON_DISTANCE;
+            if let Some(minion) = spawn_enemy(
+                &enemy_definitions,
+                &weapon_assets,
+                boss.minion,
+                &boss.difficulty,
+                &mut commands,
+                Transform::from_translation(boss_translation + offset.extend(0.0)),
+            ) {
+                // the room has already started, so `enemy_enable`
+                // will not enable the minion
+                commands.entity(minion).remove::<DisabledEnemy>();
+                boss.minions.push(minion);
+            }
+        }
+    }
+}
+
+// Hazard marks appear around the player
+pub fn boss_hazard_marks(
+    time: Res<Time>,
+    enemy_resources: Res<EnemyResources>,
+    player: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    mut bosses: Query<BossQuery, Without<DisabledEnemy>>,
+    mut combat_rng: ResMut<CombatRng>,
+    mut commands: Commands,
+) {
+    let Ok(player_transform) = player.get_single() else {
+        return;
+    };
+
+    for mut item in bosses.iter_mut() {
+        if !item.can_attack() || item.boss.phase != BossPhase::Hazards {
+            continue;
+        }
+
+        item.boss.hazard_timer.tick(time.delta());
+        if item.boss.hazard_timer.just_finished() {
+            let offset = Vec2::new(
+                combat_rng
+                    .0
+                    .gen_range(-BOSS_HAZARD_SPREAD..=BOSS_HAZARD_SPREAD),
+                combat_rng
+                    .0
+                    .gen_range(-BOSS_HAZARD_SPREAD..=BOSS_HAZARD_SPREAD),
+            );
+            let position = player_transform.translation.truncate() + offset;
+            commands.spawn((
+                PbrBundle {
+                    mesh: enemy_resources.boss_hazard_mesh.clone(),
+                    material: enemy_resources.boss_hazard_material.clone(),
+                    transform: Transform::from_translation(position.extend(BOSS_HAZARD_HEIGHT))
+                        .with_rotation(Quat::from_rotation_x(std::f32::consts::FRAC_PI_2))
+                        .with_scale(Vec3::new(BOSS_HAZARD_RADIUS, 1.0, BOSS_HAZARD_RADIUS)),
+                    ..default()
+                },
+                BossHazard {
+                    source: item.entity,
+                    explosion_translation: position.extend(player_transform.translation.z),
+                    timer: Timer::from_seconds(BOSS_HAZARD_DELAY, TimerMode::Once),
+                },
+                LevelObject,
+            ));
+        }
+    }
+}
+
+// Hazards explode even if the boss is already dead
+pub fn boss_hazards(
+    time: Res<Time>,
+    mut hazards: Query<(Entity, &mut BossHazard)>,
+    mut commands: Commands,
+    mut explosion_events: EventWriter<ExplosionEvent>,
+) {
+    for (hazard_entity, mut hazard) in hazards.iter_mut() {
+        hazard.timer.tick(time.delta());
+        if !hazard.timer.finished() {
+            continue;
+        }
+
+        explosion_events.send(ExplosionEvent {
+            source: hazard.source,
+            translation: hazard.explosion_translation,
+            radius: BOSS_HAZARD_RADIUS,
+            damage: BOSS_HAZARD_DAMAGE,
+            kind: DamageKind::Fire,
+        });
+        commands.entity(hazard_entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,400 @@
+use std::collections::HashSet;
+
+use bevy::{ecs::query::WorldQuery, prelude::*};
+
+use crate::{
+    damage::{DamageEvent, Health},
+    player::Player,
+};
+
+use super::{
+    perception::EnemyPerception,
+    squad::{squad_min_distance, SquadMember},
+    DisabledEnemy, Enemy,
+};
+
+// Enemies retreat when health drops below this fraction
+const BRAIN_RETREAT_HEALTH: f32 = 0.3;
+// Distances are compared squared, same as `min_distance`,
+// so these are multipliers of the squared min distance.
+const BRAIN_RETREAT_DISTANCE: f32 = 4.0;
+const BRAIN_STRAFE_EXIT_DISTANCE: f32 = 2.0;
+
+const BRAIN_IDLE_TIME: f32 = 3.0;
+const BRAIN_PATROL_TIME: f32 = 6.0;
+const BRAIN_ALERT_TIME: f32 = 5.0;
+// Time without seeing the player before enemy stops chasing
+const BRAIN_LOSE_TIME: f32 = 4.0;
+const BRAIN_STRAFE_SWITCH_TIME: f32 = 1.5;
+
+const BRAIN_PATROL_DISTANCE: f32 = 20.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnemyState {
+    Idle,
+    Patrol,
+    Alert,
+    Chase,
+    Strafe,
+    Retreat,
+}
+
+impl EnemyState {
+    // Enemy knows where the player is and fights
+    pub fn is_engaged(self) -> bool {
+        matches!(
+            self,
+            EnemyState::Chase | EnemyState::Strafe | EnemyState::Retreat
+        )
+    }
+}
+
+// Everything brain knows about the world in this frame
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BrainInput {
+    pub sees_player: bool,
+    pub heard_noise: bool,
+    pub damaged: bool,
+    pub player_distance_squared: f32,
+    pub min_distance: f32,
+    pub health_fraction: f32,
+    // Time spent in the current state
+    pub state_time: f32,
+    // Time since player was seen last time
+    pub lost_time: f32,
+}
+
+// Transition table of the enemy state machine.
+pub fn next_state(state: EnemyState, input: BrainInput) -> EnemyState {
+    let low_health = input.health_fraction < BRAIN_RETREAT_HEALTH;
+    let too_close = input.player_distance_squared < input.min_distance * BRAIN_RETREAT_DISTANCE;
+    let lost = !input.sees_player && BRAIN_LOSE_TIME < input.lost_time;
+
+    match state {
+        EnemyState::Idle | EnemyState::Patrol | EnemyState::Alert
+            if input.sees_player || input.damaged =>
+        {
+            EnemyState::Chase
+        }
+        EnemyState::Idle | EnemyState::Patrol if input.heard_noise => EnemyState::Alert,
+        EnemyState::Idle if BRAIN_IDLE_TIME < input.state_time => EnemyState::Patrol,
+        EnemyState::Patrol if BRAIN_PATROL_TIME < input.state_time => EnemyState::Idle,
+        EnemyState::Alert if BRAIN_ALERT_TIME < input.state_time => EnemyState::Patrol,
+        EnemyState::Chase | EnemyState::Strafe if low_health && too_close => EnemyState::Retreat,
+        EnemyState::Chase | EnemyState::Strafe | EnemyState::Retreat if lost => EnemyState::Alert,
+        EnemyState::Chase
+            if input.sees_player && input.player_distance_squared <= input.min_distance =>
+        {
+            EnemyState::Strafe
+        }
+        EnemyState::Strafe
+            if !input.sees_player
+                || !low_health
+                    && input.min_distance * BRAIN_STRAFE_EXIT_DISTANCE
+                        < input.player_distance_squared =>
+        {
+            EnemyState::Chase
+        }
+        EnemyState::Retreat if !too_close => EnemyState::Strafe,
+        _ => state,
+    }
+}
+
+#[derive(Component)]
+pub struct EnemyBrain {
+    pub state: EnemyState,
+    // Last known player position or the source of a noise
+    pub target: Option<Vec2>,
+    max_health: i32,
+    state_time: f32,
+    lost_time: f32,
+    patrol_points: [Vec2; 2],
+    patrol_index: usize,
+    strafe_sign: f32,
+}
+
+impl EnemyBrain {
+    pub fn new(position: Vec2, max_health: i32) -> Self {
+        Self {
+            state: EnemyState::Idle,
+            target: None,
+            max_health,
+            state_time: 0.0,
+            lost_time: 0.0,
+            patrol_points: [position, position + Vec2::X * BRAIN_PATROL_DISTANCE],
+            patrol_index: 0,
+            strafe_sign: 1.0,
+        }
+    }
+
+    pub fn patrol_point(&self) -> Vec2 {
+        self.patrol_points[self.patrol_index]
+    }
+
+    pub fn next_patrol_point(&mut self) {
+        self.patrol_index = (self.patrol_index + 1) % self.patrol_points.len();
+    }
+
+    // 1.0 or -1.0, changes periodically while strafing
+    pub fn strafe_sign(&self) -> f32 {
+        self.strafe_sign
+    }
+
+    // Position enemy walks to in the current state
+    pub fn move_target(&self) -> Option<Vec2> {
+        match self.state {
+            EnemyState::Patrol => Some(self.patrol_point()),
+            EnemyState::Alert | EnemyState::Chase => self.target,
+            EnemyState::Idle | EnemyState::Strafe | EnemyState::Retreat => None,
+        }
+    }
+
+    fn update(&mut self, mut input: BrainInput, dt: f32) {
+        self.state_time += dt;
+        if input.sees_player {
+            self.lost_time = 0.0;
+        } else {
+            self.lost_time += dt;
+        }
+        input.state_time = self.state_time;
+        input.lost_time = self.lost_time;
+
+        if self.state == EnemyState::Strafe {
+            let first_half =
+                self.state_time % (BRAIN_STRAFE_SWITCH_TIME * 2.0) < BRAIN_STRAFE_SWITCH_TIME;
+            self.strafe_sign = if first_half { 1.0 } else { -1.0 };
+        }
+
+        let state = next_state(self.state, input);
+        if state != self.state {
+            self.state = state;
+            self.state_time = 0.0;
+        }
+    }
+}
+
+// Everything the brain reads to pick the next state
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+pub struct BrainQuery {
+    entity: Entity,
+    enemy: &'static Enemy,
+    health: &'static Health,
+    transform: &'static Transform,
+    perception: &'static EnemyPerception,
+    squad_member: Option<&'static SquadMember>,
+    brain: &'static mut EnemyBrain,
+}
+
+pub fn enemy_brain_update(
+    time: Res<Time>,
+    player: Query<&Transform, With<Player>>,
+    mut enemies: Query<BrainQuery, Without<DisabledEnemy>>,
+    mut damage_events: EventReader<DamageEvent>,
+) {
+    let damaged = damage_events
+        .read()
+        .map(|e| e.entity)
+        .collect::<HashSet<_>>();
+
+    let Ok(player_transform) = player.get_single() else {
+        return;
+    };
+    let player_position = player_transform.translation.xy();
+
+    for BrainQueryItem {
+        entity: enemy_entity,
+        enemy,
+        health,
+        transform: enemy_transform,
+        perception: enemy_perception,
+        squad_member,
+        mut brain,
+    } in enemies.iter_mut()
+    {
+        let to_player = player_position - enemy_transform.translation.xy();
+        let sees_player = enemy_perception.sees_player;
+        let is_damaged = damaged.contains(&enemy_entity);
+
+        if sees_player || is_damaged {
+            brain.target = Some(player_position);
+        } else if let (Some(noise), false) =
+            (enemy_perception.heard_noise, brain.state.is_engaged())
+        {
+            brain.target = Some(noise);
+        }
+
+        let input = BrainInput {
+            sees_player,
+            heard_noise: enemy_perception.heard_noise.is_some(),
+            damaged: is_damaged,
+            player_distance_squared: to_player.length_squared(),
+            min_distance: squad_min_distance(enemy.min_distance, squad_member),
+            health_fraction: health.health as f32 / brain.max_health as f32,
+            ..default()
+        };
+        brain.update(input, time.delta_seconds());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIN_DISTANCE: f32 = 100.0;
+
+    // Healthy enemy far from the player it does not see
+    fn input() -> BrainInput {
+        BrainInput {
+            player_distance_squared: MIN_DISTANCE * 100.0,
+            min_distance: MIN_DISTANCE,
+            health_fraction: 1.0,
+            ..default()
+        }
+    }
+
+    fn seen_at(player_distance_squared: f32) -> BrainInput {
+        BrainInput {
+            sees_player: true,
+            player_distance_squared,
+            ..input()
+        }
+    }
+
+    fn lost() -> BrainInput {
+        BrainInput {
+            lost_time: BRAIN_LOSE_TIME + 1.0,
+            ..input()
+        }
+    }
+
+    fn low_health(input: BrainInput) -> BrainInput {
+        BrainInput {
+            health_fraction: BRAIN_RETREAT_HEALTH / 2.0,
+            ..input
+        }
+    }
+
+    #[test]
+    fn idle_transitions() {
+        let state = EnemyState::Idle;
+        assert_eq!(next_state(state, input()), EnemyState::Idle);
+        assert_eq!(next_state(state, seen_at(1.0)), EnemyState::Chase);
+        let damaged = BrainInput {
+            damaged: true,
+            ..input()
+        };
+        assert_eq!(next_state(state, damaged), EnemyState::Chase);
+        let heard = BrainInput {
+            heard_noise: true,
+            ..input()
+        };
+        assert_eq!(next_state(state, heard), EnemyState::Alert);
+        let bored = BrainInput {
+            state_time: BRAIN_IDLE_TIME + 1.0,
+            ..input()
+        };
+        assert_eq!(next_state(state, bored), EnemyState::Patrol);
+    }
+
+    #[test]
+    fn patrol_transitions() {
+        let state = EnemyState::Patrol;
+        assert_eq!(next_state(state, input()), EnemyState::Patrol);
+        assert_eq!(next_state(state, seen_at(1.0)), EnemyState::Chase);
+        let damaged = BrainInput {
+            damaged: true,
+            ..input()
+        };
+        assert_eq!(next_state(state, damaged), EnemyState::Chase);
+        let heard = BrainInput {
+            heard_noise: true,
+            ..input()
+        };
+        assert_eq!(next_state(state, heard), EnemyState::Alert);
+        let done = BrainInput {
+            state_time: BRAIN_PATROL_TIME + 1.0,
+            ..input()
+        };
+        assert_eq!(next_state(state, done), EnemyState::Idle);
+    }
+
+    #[test]
+    fn alert_transitions() {
+        let state = EnemyState::Alert;
+        assert_eq!(next_state(state, input()), EnemyState::Alert);
+        assert_eq!(next_state(state, seen_at(1.0)), EnemyState::Chase);
+        let damaged = BrainInput {
+            damaged: true,
+            ..input()
+        };
+        assert_eq!(next_state(state, damaged), EnemyState::Chase);
+        let heard = BrainInput {
+            heard_noise: true,
+            ..input()
+        };
+        assert_eq!(next_state(state, heard), EnemyState::Alert);
+        let calm = BrainInput {
+            state_time: BRAIN_ALERT_TIME + 1.0,
+            ..input()
+        };
+        assert_eq!(next_state(state, calm), EnemyState::Patrol);
+    }
+
+    #[test]
+    fn chase_transitions() {
+        let state = EnemyState::Chase;
+        assert_eq!(
+            next_state(state, seen_at(MIN_DISTANCE * 10.0)),
+            EnemyState::Chase
+        );
+        assert_eq!(next_state(state, input()), EnemyState::Chase);
+        assert_eq!(
+            next_state(state, seen_at(MIN_DISTANCE / 2.0)),
+            EnemyState::Strafe
+        );
+        assert_eq!(
+            next_state(state, low_health(seen_at(MIN_DISTANCE / 2.0))),
+            EnemyState::Retreat
+        );
+        assert_eq!(next_state(state, lost()), EnemyState::Alert);
+    }
+
+    #[test]
+    fn strafe_transitions() {
+        let state = EnemyState::Strafe;
+        assert_eq!(
+            next_state(state, seen_at(MIN_DISTANCE * 1.5)),
+            EnemyState::Strafe
+        );
+        assert_eq!(
+            next_state(state, seen_at(MIN_DISTANCE * 3.0)),
+            EnemyState::Chase
+        );
+        assert_eq!(next_state(state, input()), EnemyState::Chase);
+        assert_eq!(
+            next_state(state, low_health(seen_at(MIN_DISTANCE / 2.0))),
+            EnemyState::Retreat
+        );
+        // wounded enemies keep strafing instead of chasing
+        assert_eq!(
+            next_state(state, low_health(seen_at(MIN_DISTANCE * 5.0))),
+            EnemyState::Strafe
+        );
+        assert_eq!(next_state(state, lost()), EnemyState::Alert);
+    }
+
+    #[test]
+    fn retreat_transitions() {
+        let state = EnemyState::Retreat;
+        assert_eq!(
+            next_state(state, low_health(seen_at(MIN_DISTANCE / 2.0))),
+            EnemyState::Retreat
+        );
+        assert_eq!(
+            next_state(state, low_health(seen_at(MIN_DISTANCE * 5.0))),
+            EnemyState::Strafe
+        );
+        assert_eq!(next_state(state, lost()), EnemyState::Alert);
+    }
+}
@@ -0,0 +1,424 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+use crate::{damage::Resistances, difficulty::difficulty_curve, weapons::WeaponType};
+
+use super::{loot::LootTable, squad::SquadPlacement, EnemyType};
+
+// Stats of one enemy type. It is stored as a RON file
+// in the `enemies/definitions` folder:
+// (
+//     id: '1',
+//     name: "Small fridge",
+//     behavior: Shooter,
+//     minion: Some('1'),
+//     squad: Any,
+//     scene: "enemies/small_fridge.glb#Scene0",
+//     scale: 1.5,
+//     collider: (1.0, 1.0, 1.5),
+//     health: 50,
+//     speed: 20.0,
+//     rotation_speed: 4.0,
+//     min_distance: 400.0,
+//     weapon: Some((
+//         weapon_type: Pistol,
+//         offset: (1.0, 1.2, 0.5),
+//         aim: (spread: 0.08, reaction_time: 0.4, burst: 3, burst_pause: 0.8, lead: 1.0),
+//     )),
+//     parts: (2, 2, 2),
+//     death_gap: (0.3, 0.3, 0.3),
+//     death_pulse: 0.8,
+//     part_color: (1.0, 1.0, 0.0),
+//     loot: (nothing: 4, weapon: 4, ammo: 2, health: 1, power_up: 0),
+//     resistances: (ballistic: 1.0, pellet: 1.0, blunt: 1.0, explosive: 1.0, fire: 1.0),
+// )
+// `id` is the character of the enemy in room files, so it can not
+// be a character rooms use for other cells. Spawners and bosses
+// summon `minion` enemies and splitting elites break into them.
+// Generated rooms spawn enemies from the `enemy_mix` of the
+// difficulty curve. `collider` is half of the enemy size. Enemy
+// breaks into `parts` pieces of `part_color` when it dies and
+// drops one item rolled from the `loot` table. Enemies with
+// a weapon shoot it as described by its `aim`.
+#[derive(Debug, Clone, Asset, TypePath)]
+pub struct EnemyDefinition {
+    pub enemy_type: EnemyType,
+    pub name: String,
+    pub behavior: EnemyBehavior,
+    pub minion: Option<EnemyType>,
+    pub squad: SquadPlacement,
+    pub scene: Handle<Scene>,
+    pub scale: f32,
+    pub collider: Vec3,
+    pub health: i32,
+    pub speed: f32,
+    pub rotation_speed: f32,
+    pub min_distance: f32,
+    pub weapon: Option<EnemyWeaponDefinition>,
+    pub parts: UVec3,
+    pub death_gap: Vec3,
+    pub death_pulse: f32,
+    pub part_color: Color,
+    pub loot: LootTable,
+    pub resistances: Resistances,
+}
+
+impl EnemyDefinition {
+    pub fn dimention(&self) -> Vec3 {
+        self.collider * 2.0
+    }
+
+    pub fn part_dimention(&self) -> Vec3 {
+        self.dimention() / self.parts.as_vec3()
+    }
+
+    pub fn death_gap_delta(&self) -> Vec3 {
+        self.death_gap / self.parts.as_vec3()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum EnemyBehavior {
+    // Walks around and shoots its weapon
+    #[default]
+    Shooter,
+    // Explodes next to the player
+    Kamikaze,
+    // Stands still and summons minions
+    Spawner,
+    // Goes through the boss phases
+    Boss,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EnemyWeaponDefinition {
+    pub weapon_type: WeaponType,
+    pub offset: Vec3,
+    pub aim: EnemyAimDefinition,
+}
+
+// How well enemy shoots its weapon
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct EnemyAimDefinition {
+    // Max angle in radians shots deviate from the aim
+    pub spread: f32,
+    // Delay between seeing the player and the first shot
+    pub reaction_time: f32,
+    // Shots in one burst
+    pub burst: u32,
+    pub burst_pause: f32,
+    // Fraction of the player movement enemy accounts for
+    pub lead: f32,
+}
+
+// Layout of the definition file
+#[derive(Deserialize)]
+struct EnemyDefinitionFile {
+    id: char,
+    name: String,
+    behavior: EnemyBehavior,
+    minion: Option<char>,
+    squad: SquadPlacement,
+    scene: String,
+    scale: f32,
+    collider: (f32, f32, f32),
+    health: i32,
+    speed: f32,
+    rotation_speed: f32,
+    min_distance: f32,
+    weapon: Option<EnemyWeaponDefinitionFile>,
+    parts: (u32, u32, u32),
+    death_gap: (f32, f32, f32),
+    death_pulse: f32,
+    part_color: (f32, f32, f32),
+    loot: LootTable,
+    resistances: Resistances,
+}
+
+#[derive(Deserialize)]
+struct EnemyWeaponDefinitionFile {
+    weapon_type: WeaponType,
+    offset: (f32, f32, f32),
+    aim: EnemyAimDefinition,
+}
+
+#[derive(Debug)]
+pub enum EnemyDefinitionError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    InvalidId(char),
+    NoMinion,
+    NoParts,
+    NoLoot,
+    NotPositive { field: &'static str },
+}
+
+impl Display for EnemyDefinitionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EnemyDefinitionError::Io(e) => write!(f, "could not read enemy definition: {e}"),
+            EnemyDefinitionError::Ron(e) => write!(f, "invalid enemy definition: {e}"),
+            EnemyDefinitionError::InvalidId(id) => {
+                write!(f, "'{id}' can not be an enemy id")
+            }
+            EnemyDefinitionError::NoMinion => {
+                write!(f, "spawners and bosses must have a minion")
+            }
+            EnemyDefinitionError::NoParts => write!(f, "enemy must have at least one part"),
+            EnemyDefinitionError::NoLoot => write!(f, "enemy loot weights can not all be 0"),
+            EnemyDefinitionError::NotPositive { field } => {
+                write!(f, "enemy '{field}' must be positive")
+            }
+        }
+    }
+}
+
+impl std::error::Error for EnemyDefinitionError {}
+
+impl From<std::io::Error> for EnemyDefinitionError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<ron::error::SpannedError> for EnemyDefinitionError {
+    fn from(value: ron::error::SpannedError) -> Self {
+        Self::Ron(value)
+    }
+}
+
+#[derive(Default)]
+pub struct EnemyDefinitionLoader;
+
+impl AssetLoader for EnemyDefinitionLoader {
+    type Asset = EnemyDefinition;
+    type Settings = ();
+    type Error = EnemyDefinitionError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let file = ron::de::from_bytes::<EnemyDefinitionFile>(&bytes)?;
+            parse_enemy_definition(file, load_context)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["enemy.ron"]
+    }
+}
+
+fn parse_enemy_definition(
+    file: EnemyDefinitionFile,
+    load_context: &mut LoadContext,
+) -> Result<EnemyDefinition, EnemyDefinitionError> {
+    let enemy_type =
+        EnemyType::from_char(file.id).ok_or(EnemyDefinitionError::InvalidId(file.id))?;
+    let minion = file
+        .minion
+        .map(|id| EnemyType::from_char(id).ok_or(EnemyDefinitionError::InvalidId(id)))
+        .transpose()?;
+    if minion.is_none() && matches!(file.behavior, EnemyBehavior::Spawner | EnemyBehavior::Boss) {
+        return Err(EnemyDefinitionError::NoMinion);
+    }
+    let parts = UVec3::from(file.parts);
+    if parts.min_element() == 0 {
+        return Err(EnemyDefinitionError::NoParts);
+    }
+    if file.loot.is_empty() {
+        return Err(EnemyDefinitionError::NoLoot);
+    }
+    let collider = Vec3::from(file.collider);
+    if collider.min_element() <= 0.0 {
+        return Err(EnemyDefinitionError::NotPositive { field: "collider" });
+    }
+    if file.scale <= 0.0 {
+        return Err(EnemyDefinitionError::NotPositive { field: "scale" });
+    }
+    if file.health <= 0 {
+        return Err(EnemyDefinitionError::NotPositive { field: "health" });
+    }
+    if file
+        .weapon
+        .as_ref()
+        .is_some_and(|weapon| weapon.aim.burst == 0)
+    {
+        return Err(EnemyDefinitionError::NotPositive { field: "burst" });
+    }
+
+    Ok(EnemyDefinition {
+        enemy_type,
+        name: file.name,
+        behavior: file.behavior,
+        minion,
+        squad: file.squad,
+        scene: load_context.load(file.scene),
+        scale: file.scale,
+        collider,
+        health: file.health,
+        speed: file.speed,
+        rotation_speed: file.rotation_speed,
+        min_distance: file.min_distance,
+        weapon: file.weapon.map(|weapon| EnemyWeaponDefinition {
+            weapon_type: weapon.weapon_type,
+            offset: Vec3::from(weapon.offset),
+            aim: weapon.aim,
+        }),
+        parts,
+        death_gap: Vec3::from(file.death_gap),
+        death_pulse: file.death_pulse,
+        part_color: Color::rgb(file.part_color.0, file.part_color.1, file.part_color.2),
+        loot: file.loot,
+        resistances: file.resistances,
+    })
+}
+
+// Debris of one enemy type
+pub struct EnemyParts {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<StandardMaterial>,
+}
+
+// Loaded definitions by enemy type. They are updated
+// when definition files are added, changed or removed.
+#[derive(Default, Resource)]
+pub struct EnemyDefinitions {
+    definitions: BTreeMap<EnemyType, EnemyDefinition>,
+    parts: BTreeMap<EnemyType, EnemyParts>,
+    // Enemy type of each loaded definition file
+    assets: HashMap<AssetId<EnemyDefinition>, EnemyType>,
+}
+
+impl EnemyDefinitions {
+    pub fn get(&self, enemy_type: EnemyType) -> Option<&EnemyDefinition> {
+        self.definitions.get(&enemy_type)
+    }
+
+    pub fn parts(&self, enemy_type: EnemyType) -> Option<&EnemyParts> {
+        self.parts.get(&enemy_type)
+    }
+
+    // All enemy types ordered by id
+    pub fn types(&self) -> impl Iterator<Item = EnemyType> + '_ {
+        self.definitions.keys().copied()
+    }
+
+    pub fn insert(
+        &mut self,
+        id: AssetId<EnemyDefinition>,
+        definition: &EnemyDefinition,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<StandardMaterial>,
+    ) {
+        // file can change the id of its enemy
+        self.remove(id);
+        if self.definitions.contains_key(&definition.enemy_type) {
+            warn!(
+                "Enemy id '{}' is used by more than one definition",
+                definition.enemy_type.0
+            );
+        }
+
+        let part_dimention = definition.part_dimention();
+        let mesh = meshes
+            .add(shape::Box::new(part_dimention.x, part_dimention.y, part_dimention.z).into());
+        let material = materials.add(definition.part_color.into());
+        self.parts
+            .insert(definition.enemy_type, EnemyParts { mesh, material });
+        self.definitions
+            .insert(definition.enemy_type, definition.clone());
+        self.assets.insert(id, definition.enemy_type);
+    }
+
+    pub fn remove(&mut self, id: AssetId<EnemyDefinition>) {
+        let Some(enemy_type) = self.assets.remove(&id) else {
+            return;
+        };
+        // other file can have the same id
+        if self.assets.values().all(|other| *other != enemy_type) {
+            self.definitions.remove(&enemy_type);
+            self.parts.remove(&enemy_type);
+        }
+    }
+
+    // Definitions refer to each other and the difficulty curve
+    // refers to them by id, so ids can only be checked after
+    // all definitions are loaded
+    pub fn check_ids(&self) {
+        for definition in self.definitions.values() {
+            if let Some(minion) = definition.minion {
+                if !self.definitions.contains_key(&minion) {
+                    warn!(
+                        "{} enemy has minion '{}' without a definition",
+                        definition.name, minion.0
+                    );
+                }
+            }
+        }
+
+        for step in difficulty_curve() {
+            for (enemy_type, _) in step.enemy_mix {
+                if !self.definitions.contains_key(enemy_type) {
+                    warn!(
+                        "Difficulty step at {} progress has enemy '{}' without a definition",
+                        step.progress, enemy_type.0
+                    );
+                }
+            }
+            let spawns_enemies = step.enemy_mix.iter().any(|(enemy_type, weight)| {
+                *weight != 0 && self.definitions.contains_key(enemy_type)
+            });
+            if !spawns_enemies {
+                error!(
+                    "Difficulty step at {} progress has no enemies to spawn",
+                    step.progress
+                );
+            }
+        }
+    }
+}
+
+pub fn enemy_definitions_reload(
+    definition_assets: Res<Assets<EnemyDefinition>>,
+    mut enemy_definitions: ResMut<EnemyDefinitions>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut asset_events: EventReader<AssetEvent<EnemyDefinition>>,
+) {
+    let mut changed = false;
+    for event in asset_events.read() {
+        match *event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => {
+                let Some(definition) = definition_assets.get(id) else {
+                    continue;
+                };
+                info!("Reloaded {} enemy definition", definition.name);
+                enemy_definitions.insert(id, definition, meshes.as_mut(), materials.as_mut());
+            }
+            AssetEvent::Removed { id } => {
+                info!("Removed enemy definition");
+                enemy_definitions.remove(id);
+            }
+            _ => continue,
+        }
+        changed = true;
+    }
+    if changed {
+        enemy_definitions.check_ids();
+    }
+}
@@ -0,0 +1,296 @@
+use bevy::prelude::*;
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    damage::{Armor, DamageEvent, Health, KillEvent, Resistances, Shield},
+    difficulty::{Difficulty, DifficultyStep},
+    level::{LevelInfo, LevelStarted},
+    player::Player,
+    run::{RunConfig, RunRng},
+    weapons::WeaponAssets,
+};
+
+use super::{
+    hit::EnemyHitReaction, spawn_enemy, DisabledEnemy, Enemy, EnemyBehavior, EnemyDefinitions,
+    EnemyWeapon,
+};
+
+// Chance of an enemy to be an elite grows
+// with the run progress up to the max
+const ELITE_CHANCE_PER_PROGRESS: f32 = 0.3;
+const ELITE_MAX_CHANCE: f32 = 0.4;
+// Chance of an elite to roll one more modifier
+const ELITE_EXTRA_MODIFIER_CHANCE: f32 = 0.25;
+const ELITE_MAX_MODIFIERS: usize = 3;
+
+// Armor stops bullets and pellets, but thrown weapons dent it
+const ELITE_ARMORED_RESISTANCES: Resistances = Resistances {
+    ballistic: 0.6,
+    pellet: 0.4,
+    blunt: 1.5,
+    explosive: 0.8,
+    fire: 1.0,
+};
+// Armor points are a fraction of the enemy health
+const ELITE_ARMOR: f32 = 0.5;
+// Fraction of the damage armor absorbs
+const ELITE_ARMOR_ABSORB: f32 = 0.5;
+const ELITE_SWIFT_SPEED: f32 = 1.5;
+// Fraction of the damage dealt to the player vampiric elite heals
+const ELITE_VAMPIRIC_HEAL: f32 = 0.5;
+// Shield points are a fraction of the enemy health
+const ELITE_SHIELD: f32 = 0.5;
+// Shield starts to recharge after this time without hits
+const ELITE_SHIELD_DELAY: f32 = 3.0;
+// Shield points per second
+const ELITE_SHIELD_RECHARGE: f32 = 20.0;
+// Distance from the splitting elite to its minions
+const ELITE_SPLIT_DISTANCE: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EliteModifier {
+    Armored,
+    Swift,
+    Vampiric,
+    Splitting,
+    Shielded,
+}
+
+impl EliteModifier {
+    const ALL: [EliteModifier; 5] = [
+        EliteModifier::Armored,
+        EliteModifier::Swift,
+        EliteModifier::Vampiric,
+        EliteModifier::Splitting,
+        EliteModifier::Shielded,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EliteModifier::Armored => "Armored",
+            EliteModifier::Swift => "Swift",
+            EliteModifier::Vampiric => "Vampiric",
+            EliteModifier::Splitting => "Splitting",
+            EliteModifier::Shielded => "Shielded",
+        }
+    }
+
+    fn tint(self) -> Vec4 {
+        match self {
+            EliteModifier::Armored => Vec4::new(0.5, 0.5, 0.6, 1.0),
+            EliteModifier::Swift => Vec4::new(1.0, 1.0, 0.3, 1.0),
+            EliteModifier::Vampiric => Vec4::new(1.0, 0.2, 0.2, 1.0),
+            EliteModifier::Splitting => Vec4::new(0.3, 1.0, 0.3, 1.0),
+            EliteModifier::Shielded => Vec4::new(0.3, 0.7, 1.0, 1.0),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Elite {
+    modifiers: Vec<EliteModifier>,
+    // Splitting elites spawn minions with the same difficulty
+    difficulty: DifficultyStep,
+    max_health: i32,
+    tinted: bool,
+}
+
+impl Elite {
+    fn new(modifiers: Vec<EliteModifier>, difficulty: DifficultyStep, max_health: i32) -> Self {
+        Self {
+            modifiers,
+            difficulty,
+            max_health,
+            tinted: false,
+        }
+    }
+
+    pub fn has(&self, modifier: EliteModifier) -> bool {
+        self.modifiers.contains(&modifier)
+    }
+
+    pub fn name(&self) -> String {
+        self.modifiers
+            .iter()
+            .map(|modifier| modifier.name())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn tint(&self) -> Vec4 {
+        self.modifiers
+            .iter()
+            .map(|modifier| modifier.tint())
+            .sum::<Vec4>()
+            / self.modifiers.len() as f32
+    }
+}
+
+// Enemies of the new room become elites when it starts
+pub fn elite_roll(
+    difficulty: Res<Difficulty>,
+    run_config: Res<RunConfig>,
+    level_info: Res<LevelInfo>,
+    mut run_rng: ResMut<RunRng>,
+    mut enemies: Query<(Entity, &mut Enemy, &Health, &mut Resistances), With<DisabledEnemy>>,
+    mut commands: Commands,
+    mut level_started_events: EventReader<LevelStarted>,
+) {
+    for _ in level_started_events.read() {
+        let progress = run_config.progress(level_info.room);
+        let chance = (progress * ELITE_CHANCE_PER_PROGRESS).clamp(0.0, ELITE_MAX_CHANCE);
+        if chance == 0.0 {
+            continue;
+        }
+        let difficulty = difficulty.step(progress);
+
+        for (enemy_entity, mut enemy, health, mut resistances) in enemies.iter_mut() {
+            if enemy.behavior == EnemyBehavior::Boss || !run_rng.0.gen_bool(chance as f64) {
+                continue;
+            }
+
+            let mut modifiers = vec![];
+            loop {
+                let modifier = *EliteModifier::ALL.choose(&mut run_rng.0).unwrap();
+                if !modifiers.contains(&modifier) {
+                    modifiers.push(modifier);
+                }
+                if ELITE_MAX_MODIFIERS <= modifiers.len()
+                    || !run_rng.0.gen_bool(ELITE_EXTRA_MODIFIER_CHANCE as f64)
+                {
+                    break;
+                }
+            }
+
+            if modifiers.contains(&EliteModifier::Swift) {
+                enemy.speed *= ELITE_SWIFT_SPEED;
+            }
+            if modifiers.contains(&EliteModifier::Shielded) {
+                commands.entity(enemy_entity).insert(Shield::new(
+                    (health.health as f32 * ELITE_SHIELD) as i32,
+                    ELITE_SHIELD_DELAY,
+                    ELITE_SHIELD_RECHARGE,
+                ));
+            }
+
+            if modifiers.contains(&EliteModifier::Armored) {
+                *resistances = resistances.stack(&ELITE_ARMORED_RESISTANCES);
+                commands.entity(enemy_entity).insert(Armor {
+                    armor: (health.health as f32 * ELITE_ARMOR) as i32,
+                    absorb: ELITE_ARMOR_ABSORB,
+                });
+            }
+
+            commands
+                .entity(enemy_entity)
+                .insert(Elite::new(modifiers, difficulty, health.health));
+        }
+    }
+}
+
+pub fn elite_update(
+    player: Query<Entity, With<Player>>,
+    weapons: Query<&Parent, With<EnemyWeapon>>,
+    mut elites: Query<(&Elite, &mut Health), Without<DisabledEnemy>>,
+    mut damage_events: EventReader<DamageEvent>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+
+    for event in damage_events.read() {
+        // vampiric elites heal when they hit the player
+        if event.entity != player {
+            continue;
+        }
+        let Some(weapon) = event.weapon else {
+            continue;
+        };
+        let Ok(weapon_parent) = weapons.get(weapon) else {
+            continue;
+        };
+        let Ok((elite, mut health)) = elites.get_mut(weapon_parent.get()) else {
+            continue;
+        };
+        if elite.has(EliteModifier::Vampiric) {
+            let heal = (event.amount as f32 * ELITE_VAMPIRIC_HEAL) as i32;
+            health.health = (health.health + heal).min(elite.max_health);
+        }
+    }
+}
+
+// Fridge scenes share their materials, so elites
+// get tinted copies once their scene is spawned
+pub fn elite_tint(
+    children: Query<&Children>,
+    enemy_weapons: Query<(), With<EnemyWeapon>>,
+    mut elites: Query<(Entity, &EnemyHitReaction, &mut Elite)>,
+    mut mesh_materials: Query<&mut Handle<StandardMaterial>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (elite_entity, hit_reaction, mut elite) in elites.iter_mut() {
+        // flashed meshes would restore the untinted materials
+        if elite.tinted || hit_reaction.is_flashing() {
+            continue;
+        }
+
+        let tint = elite.tint();
+        let mut stack = vec![elite_entity];
+        while let Some(entity) = stack.pop() {
+            if let Ok(mut mesh_material) = mesh_materials.get_mut(entity) {
+                if let Some(mut material) = materials.get(mesh_material.as_ref()).cloned() {
+                    material.base_color *= tint;
+                    *mesh_material = materials.add(material);
+                    elite.tinted = true;
+                }
+            }
+            if let Ok(entity_children) = children.get(entity) {
+                stack.extend(
+                    entity_children
+                        .iter()
+                        .filter(|child| !enemy_weapons.contains(**child)),
+                );
+            }
+        }
+    }
+}
+
+// Splitting elites break into their minions. Runs right before `enemy_die`,
+// so minions appear before the room counts its enemies.
+pub fn elite_split(
+    enemy_definitions: Res<EnemyDefinitions>,
+    weapon_assets: Res<WeaponAssets>,
+    elites: Query<(&Enemy, &Elite, &Transform)>,
+    mut commands: Commands,
+    mut kill_events: EventReader<KillEvent>,
+) {
+    for kill_event in kill_events.read() {
+        let Ok((enemy, elite, enemy_transform)) = elites.get(kill_event.entity) else {
+            continue;
+        };
+        if !elite.has(EliteModifier::Splitting) {
+            continue;
+        }
+        let Some(minion) = enemy.minion else {
+            continue;
+        };
+
+        let side = (enemy_transform.rotation * Vec3::X) * ELITE_SPLIT_DISTANCE;
+        for offset in [side, -side] {
+            let Some(minion) = spawn_enemy(
+                &enemy_definitions,
+                &weapon_assets,
+                minion,
+                &elite.difficulty,
+                &mut commands,
+                Transform::from_translation(enemy_transform.translation + offset)
+                    .with_rotation(enemy_transform.rotation),
+            ) else {
+                continue;
+            };
+            // the room has already started, so `enemy_enable`
+            // will not enable the minion
+            commands.entity(minion).remove::<DisabledEnemy>();
+        }
+    }
+}
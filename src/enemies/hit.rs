@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+
+use crate::damage::DamageEvent;
+
+use super::{DisabledEnemy, Enemy, EnemyDefinitions, EnemyResources, EnemyWeapon};
+
+// Knockback speed per point of damage for an enemy of size 1.0.
+// Size is the length of the enemy collider half extents.
+const HIT_KNOCKBACK_STRENGTH: f32 = 1.0;
+// How fast knockback speed fades per second
+const HIT_KNOCKBACK_DECAY: f32 = 8.0;
+
+// Flinching enemies do not move or shoot
+const HIT_FLINCH_TIME_PER_DAMAGE: f32 = 0.03;
+const HIT_FLINCH_MAX_TIME: f32 = 0.6;
+// Time after a flinch when enemy ignores new hits,
+// so constant fire does not lock it in place
+const HIT_FLINCH_COOLDOWN: f32 = 0.5;
+
+const HIT_FLASH_TIME_PER_DAMAGE: f32 = 0.01;
+const HIT_FLASH_MIN_TIME: f32 = 0.05;
+const HIT_FLASH_MAX_TIME: f32 = 0.25;
+
+#[derive(Default, Component)]
+pub struct EnemyHitReaction {
+    knockback: Vec2,
+    flinch_time: f32,
+    flinch_cooldown: f32,
+    flash_time: f32,
+    // Meshes of the enemy model and their own materials
+    // while they use the flash material
+    flashed: Vec<(Entity, Handle<StandardMaterial>)>,
+}
+
+impl EnemyHitReaction {
+    pub fn is_flinching(&self) -> bool {
+        0.0 < self.flinch_time
+    }
+
+    pub fn is_flashing(&self) -> bool {
+        0.0 < self.flash_time
+    }
+
+    // Velocity enemy is pushed with
+    pub fn knockback(&self) -> Vec2 {
+        self.knockback
+    }
+}
+
+// Fades knockback and flinch and restores flashed materials
+pub fn enemy_hit_recover(
+    time: Res<Time>,
+    mut enemies: Query<&mut EnemyHitReaction, Without<DisabledEnemy>>,
+    mut materials: Query<&mut Handle<StandardMaterial>>,
+) {
+    let dt = time.delta_seconds();
+    for mut reaction in enemies.iter_mut() {
+        reaction.knockback *= (-HIT_KNOCKBACK_DECAY * dt).exp();
+        reaction.flinch_time = (reaction.flinch_time - dt).max(0.0);
+        reaction.flinch_cooldown = (reaction.flinch_cooldown - dt).max(0.0);
+        reaction.flash_time = (reaction.flash_time - dt).max(0.0);
+
+        if reaction.flash_time == 0.0 {
+            for (mesh_entity, material) in reaction.flashed.drain(..) {
+                if let Ok(mut mesh_material) = materials.get_mut(mesh_entity) {
+                    *mesh_material = material;
+                }
+            }
+        }
+    }
+}
+
+pub fn enemy_hit_reaction(
+    enemy_definitions: Res<EnemyDefinitions>,
+    enemy_resources: Res<EnemyResources>,
+    children: Query<&Children>,
+    enemy_weapons: Query<(), With<EnemyWeapon>>,
+    mut enemies: Query<(&Enemy, &mut EnemyHitReaction), Without<DisabledEnemy>>,
+    mut materials: Query<&mut Handle<StandardMaterial>>,
+    mut damage_events: EventReader<DamageEvent>,
+) {
+    for event in damage_events.read() {
+        let Ok((enemy, mut reaction)) = enemies.get_mut(event.entity) else {
+            continue;
+        };
+        // shield and armor took the whole hit
+        if event.amount <= 0 {
+            continue;
+        }
+        let Some(definition) = enemy_definitions.get(enemy.enemy_type) else {
+            continue;
+        };
+        let size = definition.collider.length();
+        let damage = event.amount as f32;
+
+        reaction.knockback +=
+            event.direction.truncate().normalize_or_zero() * damage * HIT_KNOCKBACK_STRENGTH / size;
+        if reaction.flinch_cooldown == 0.0 {
+            reaction.flinch_time =
+                (damage * HIT_FLINCH_TIME_PER_DAMAGE / size).min(HIT_FLINCH_MAX_TIME);
+            reaction.flinch_cooldown = reaction.flinch_time + HIT_FLINCH_COOLDOWN;
+        }
+        reaction.flash_time = reaction.flash_time.max(
+            (damage * HIT_FLASH_TIME_PER_DAMAGE).clamp(HIT_FLASH_MIN_TIME, HIT_FLASH_MAX_TIME),
+        );
+
+        if !reaction.flashed.is_empty() {
+            continue;
+        }
+        // weapons are not flashed, because they are
+        // dropped with their materials when enemy dies
+        let mut stack = vec![event.entity];
+        while let Some(entity) = stack.pop() {
+            if let Ok(mut mesh_material) = materials.get_mut(entity) {
+                let material = std::mem::replace(
+                    mesh_material.as_mut(),
+                    enemy_resources.hit_flash_material.clone(),
+                );
+                reaction.flashed.push((entity, material));
+            }
+            if let Ok(entity_children) = children.get(entity) {
+                stack.extend(
+                    entity_children
+                        .iter()
+                        .filter(|child| !enemy_weapons.contains(**child)),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+
+use crate::{
+    damage::{DamageKind, ExplosionEvent, Health, KillEvent},
+    player::Player,
+};
+
+use super::{DisabledEnemy, Enemy, EnemyBehavior};
+
+// Kamikaze explodes when player is this close
+const KAMIKAZE_TRIGGER_DISTANCE: f32 = 6.0;
+const KAMIKAZE_EXPLOSION_RADIUS: f32 = 20.0;
+const KAMIKAZE_EXPLOSION_DAMAGE: i32 = 60;
+
+// Detonated kamikaze is killed, so `enemy_die`
+// spawns its debris.
+pub fn kamikaze_detonate(
+    player: Query<&Transform, With<Player>>,
+    enemies: Query<(Entity, &Enemy, &Transform, &Health), Without<DisabledEnemy>>,
+    mut commands: Commands,
+    mut kill_events: EventWriter<KillEvent>,
+    mut explosion_events: EventWriter<ExplosionEvent>,
+) {
+    let Ok(player_transform) = player.get_single() else {
+        return;
+    };
+
+    // killed enemies have no health, so they do not explode again
+    for (enemy_entity, enemy, enemy_transform, _) in enemies.iter() {
+        if enemy.behavior != EnemyBehavior::Kamikaze {
+            continue;
+        }
+        let distance = enemy_transform
+            .translation
+            .xy()
+            .distance(player_transform.translation.xy());
+        if KAMIKAZE_TRIGGER_DISTANCE < distance {
+            continue;
+        }
+
+        commands.entity(enemy_entity).remove::<Health>();
+        kill_events.send(KillEvent {
+            entity: enemy_entity,
+        });
+        explosion_events.send(ExplosionEvent {
+            source: enemy_entity,
+            translation: enemy_transform.translation,
+            radius: KAMIKAZE_EXPLOSION_RADIUS,
+            damage: KAMIKAZE_EXPLOSION_DAMAGE,
+            kind: DamageKind::Explosive,
+        });
+    }
+}
@@ -0,0 +1,166 @@
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::difficulty::DifficultyStep;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Loot {
+    Nothing,
+    // Weapon the enemy was holding
+    Weapon,
+    Ammo,
+    Health,
+    PowerUp,
+}
+
+// Weights of enemy drops. It is a part of
+// the enemy definition:
+// loot: (nothing: 4, weapon: 4, ammo: 2, health: 1, power_up: 0)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+pub struct LootTable {
+    pub nothing: u32,
+    pub weapon: u32,
+    pub ammo: u32,
+    pub health: u32,
+    pub power_up: u32,
+}
+
+impl LootTable {
+    pub fn is_empty(&self) -> bool {
+        self.nothing + self.weapon + self.ammo + self.health + self.power_up == 0
+    }
+
+    // Drops are scaled by the difficulty, `Nothing` is not. Enemies
+    // without a weapon can not drop one.
+    pub fn weights(&self, difficulty: &DifficultyStep, has_weapon: bool) -> [(Loot, f32); 5] {
+        let weapon = if has_weapon { self.weapon } else { 0 };
+        [
+            (Loot::Nothing, self.nothing as f32),
+            (Loot::Weapon, weapon as f32 * difficulty.drops),
+            (Loot::Ammo, self.ammo as f32 * difficulty.drops),
+            (Loot::Health, self.health as f32 * difficulty.drops),
+            (Loot::PowerUp, self.power_up as f32 * difficulty.drops),
+        ]
+    }
+
+    pub fn roll(&self, difficulty: &DifficultyStep, has_weapon: bool, rng: &mut impl Rng) -> Loot {
+        let weights = self.weights(difficulty, has_weapon);
+        let total = weights.iter().map(|(_, weight)| weight).sum::<f32>();
+        if total <= 0.0 {
+            return Loot::Nothing;
+        }
+
+        let mut random = rng.gen_range(0.0..total);
+        for (loot, weight) in weights {
+            if random < weight {
+                return loot;
+            }
+            random -= weight;
+        }
+        Loot::Nothing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::difficulty::Difficulty;
+
+    use super::*;
+
+    const TABLE: LootTable = LootTable {
+        nothing: 4,
+        weapon: 4,
+        ammo: 2,
+        health: 1,
+        power_up: 1,
+    };
+
+    fn difficulty(drops: f32) -> DifficultyStep {
+        DifficultyStep {
+            drops,
+            ..Difficulty::Normal.step(0.0)
+        }
+    }
+
+    #[test]
+    fn weights_scale_with_drops() {
+        assert_eq!(
+            TABLE.weights(&difficulty(2.0), true),
+            [
+                (Loot::Nothing, 4.0),
+                (Loot::Weapon, 8.0),
+                (Loot::Ammo, 4.0),
+                (Loot::Health, 2.0),
+                (Loot::PowerUp, 2.0),
+            ]
+        );
+        assert_eq!(
+            TABLE.weights(&difficulty(0.5), true),
+            [
+                (Loot::Nothing, 4.0),
+                (Loot::Weapon, 2.0),
+                (Loot::Ammo, 1.0),
+                (Loot::Health, 0.5),
+                (Loot::PowerUp, 0.5),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_weapon_drop_without_weapon() {
+        let weights = TABLE.weights(&difficulty(1.0), false);
+        assert_eq!(weights[1], (Loot::Weapon, 0.0));
+
+        let weapon_only = LootTable {
+            weapon: 1,
+            ..LootTable::default()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            assert_eq!(
+                weapon_only.roll(&difficulty(1.0), false, &mut rng),
+                Loot::Nothing
+            );
+        }
+    }
+
+    #[test]
+    fn empty_table_drops_nothing() {
+        let empty = LootTable::default();
+        assert!(empty.is_empty());
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            assert_eq!(empty.roll(&difficulty(1.0), true, &mut rng), Loot::Nothing);
+        }
+    }
+
+    #[test]
+    fn roll_follows_weights() {
+        const ROLLS: usize = 12000;
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut counts = [0usize; 5];
+        for _ in 0..ROLLS {
+            let loot = TABLE.roll(&difficulty(1.0), true, &mut rng);
+            let index = TABLE
+                .weights(&difficulty(1.0), true)
+                .iter()
+                .position(|(other, _)| *other == loot)
+                .unwrap();
+            counts[index] += 1;
+        }
+
+        let weights = TABLE
+            .weights(&difficulty(1.0), true)
+            .map(|(_, weight)| weight);
+        let total = weights.iter().sum::<f32>();
+        for (count, weight) in counts.iter().zip(weights) {
+            let expected = ROLLS as f32 * weight / total;
+            assert!(
+                (*count as f32 - expected).abs() < expected * 0.1,
+                "{counts:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,696 @@
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_rapier3d::prelude::*;
+mod aim;
+mod boss;
+mod brain;
+mod definition;
+mod elite;
+mod hit;
+mod kamikaze;
+mod loot;
+mod perception;
+mod spawner;
+mod squad;
+mod steering;
+
+use aim::{enemy_shoot, EnemyAim};
+use boss::{boss_hazard_marks, boss_hazards, boss_phase_update, boss_shoot, boss_summon};
+use brain::{enemy_brain_update, EnemyBrain, EnemyState};
+use definition::{enemy_definitions_reload, EnemyBehavior, EnemyDefinition, EnemyDefinitionLoader};
+use elite::{elite_roll, elite_split, elite_tint, elite_update};
+use hit::{enemy_hit_reaction, enemy_hit_recover, EnemyHitReaction};
+use kamikaze::kamikaze_detonate;
+use loot::Loot;
+use perception::{enemy_perception, EnemyPerception};
+use spawner::{enemy_spawner_update, EnemySpawner};
+use squad::{
+    squad_assign, squad_min_distance, squad_move_target, squad_update, SquadMember, SquadPlacement,
+};
+use steering::{EnemySteering, STEERING_SEPARATION_WEIGHT};
+
+pub use boss::{Boss, BossPhase, BossPhaseEvent};
+pub use definition::EnemyDefinitions;
+pub use elite::Elite;
+
+use crate::{
+    damage::{Health, KillEvent, Resistances},
+    difficulty::{DifficultyStep, RoomDifficulty},
+    level::{navigation::NavGrid, LevelObject, LevelStarted},
+    pickups::{spawn_pickup, PickupResources, PickupType},
+    player::Player,
+    run::CombatRng,
+    weapons::{
+        attach_weapon, floating::FloatingObjectBundle, Ammo, WeaponAssets, WeaponAttackTimer,
+        WeaponBundle, WeaponModel, WeaponType,
+    },
+    GlobalState, COLLISION_GROUP_ENEMY, COLLISION_GROUP_LEVEL, COLLISION_GROUP_PROJECTILES,
+};
+
+// Boss holds a shotgun in the other hand
+const ENEMY_BOSS_SECOND_WEAPON_OFFSET: Vec3 = Vec3::new(-2.0, 2.2, 0.5);
+
+// How often enemies look for a new path to the target
+const ENEMY_REPATH_TIME: f32 = 0.5;
+// Waypoint is reached when enemy is this close to it
+const ENEMY_WAYPOINT_DISTANCE: f32 = 2.5;
+
+pub struct EnemiesPlugin;
+
+impl Plugin for EnemiesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<EnemyDefinition>();
+        app.init_asset_loader::<EnemyDefinitionLoader>();
+
+        app.add_collection_to_loading_state::<_, EnemyAssets>(GlobalState::AssetLoading);
+
+        app.add_event::<BossPhaseEvent>();
+
+        app.insert_resource(EnemySteering::from_env());
+
+        app.add_systems(
+            OnTransition {
+                from: GlobalState::AssetLoading,
+                to: GlobalState::MainMenu,
+            },
+            init_resources,
+        );
+
+        app.add_systems(
+            Update,
+            (
+                // elites are rolled from enemies that are still disabled
+                (elite_roll, enemy_enable).chain(),
+                (
+                    enemy_perception,
+                    enemy_brain_update,
+                    enemy_hit_recover,
+                    enemy_hit_reaction,
+                    squad_assign,
+                    squad_update,
+                    enemy_update_path,
+                    enemy_move,
+                )
+                    .chain(),
+                enemy_shoot,
+                kamikaze_detonate,
+                enemy_spawner_update,
+                elite_update,
+                elite_tint,
+                (
+                    boss_phase_update,
+                    boss_shoot,
+                    boss_summon,
+                    boss_hazard_marks,
+                    boss_hazards,
+                )
+                    .chain(),
+                (elite_split, enemy_die).chain(),
+            )
+                .run_if(in_state(GlobalState::InGame)),
+        );
+
+        app.add_systems(
+            Update,
+            enemy_definitions_reload.run_if(resource_exists::<EnemyDefinitions>()),
+        );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct EnemyAssets {
+    #[asset(path = "enemies/definitions", collection(typed))]
+    pub definitions: Vec<Handle<EnemyDefinition>>,
+}
+
+#[derive(Resource)]
+pub struct EnemyResources {
+    hit_flash_material: Handle<StandardMaterial>,
+    boss_hazard_mesh: Handle<Mesh>,
+    boss_hazard_material: Handle<StandardMaterial>,
+}
+
+// Id of the enemy definition. It is the character
+// the enemy is written with in room files.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EnemyType(pub char);
+
+impl EnemyType {
+    // Characters rooms use for weapons and doors
+    const RESERVED: &'static str = "psmTBLR";
+
+    pub fn from_char(c: char) -> Option<Self> {
+        (c.is_ascii_alphanumeric() && !Self::RESERVED.contains(c)).then_some(Self(c))
+    }
+}
+
+#[derive(Default, Component)]
+pub struct Enemy {
+    enemy_type: EnemyType,
+    behavior: EnemyBehavior,
+    minion: Option<EnemyType>,
+    squad: SquadPlacement,
+    speed: f32,
+    rotation_speed: f32,
+    min_distance: f32,
+    weapon_type: Option<WeaponType>,
+    attached_weapon: Option<Entity>,
+}
+
+// Waypoints to the brain target found on the NavGrid
+#[derive(Component)]
+pub struct EnemyPath {
+    waypoints: Vec<Vec2>,
+    repath_timer: Timer,
+}
+
+impl Default for EnemyPath {
+    fn default() -> Self {
+        Self {
+            waypoints: vec![],
+            repath_timer: Timer::from_seconds(ENEMY_REPATH_TIME, TimerMode::Repeating),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct EnemyWeapon;
+
+#[derive(Component)]
+pub struct DisabledEnemy;
+
+#[derive(Bundle)]
+pub struct EnemyBundle {
+    rigid_body: RigidBody,
+    collider: Collider,
+    collision_groups: CollisionGroups,
+    controller: KinematicCharacterController,
+    locked_axis: LockedAxes,
+    enemy: Enemy,
+    path: EnemyPath,
+    brain: EnemyBrain,
+    perception: EnemyPerception,
+    aim: EnemyAim,
+    hit_reaction: EnemyHitReaction,
+
+    scene_bundle: SceneBundle,
+    health: Health,
+    resistances: Resistances,
+    disabled: DisabledEnemy,
+
+    level_object: LevelObject,
+}
+
+impl Default for EnemyBundle {
+    fn default() -> Self {
+        Self {
+            rigid_body: RigidBody::KinematicPositionBased,
+            collider: Collider::default(),
+            collision_groups: CollisionGroups::new(
+                COLLISION_GROUP_ENEMY,
+                COLLISION_GROUP_LEVEL | COLLISION_GROUP_PROJECTILES,
+            ),
+            controller: KinematicCharacterController {
+                up: Vec3::Z,
+                offset: CharacterLength::Relative(0.1),
+                filter_flags: QueryFilterFlags::EXCLUDE_SENSORS | QueryFilterFlags::EXCLUDE_DYNAMIC,
+                ..default()
+            },
+            locked_axis: LockedAxes::TRANSLATION_LOCKED_Z,
+            enemy: Enemy::default(),
+            path: EnemyPath::default(),
+            brain: EnemyBrain::new(Vec2::ZERO, 0),
+            perception: EnemyPerception::default(),
+            aim: EnemyAim::default(),
+            hit_reaction: EnemyHitReaction::default(),
+
+            scene_bundle: SceneBundle::default(),
+            health: Health::default(),
+            resistances: Resistances::default(),
+            disabled: DisabledEnemy,
+
+            level_object: LevelObject,
+        }
+    }
+}
+
+fn init_resources(
+    enemy_assets: Res<EnemyAssets>,
+    definition_assets: Res<Assets<EnemyDefinition>>,
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let mut enemy_definitions = EnemyDefinitions::default();
+    for handle in enemy_assets.definitions.iter() {
+        let definition = definition_assets.get(handle).unwrap();
+        enemy_definitions.insert(handle.id(), definition, meshes.as_mut(), materials.as_mut());
+    }
+    enemy_definitions.check_ids();
+    commands.insert_resource(enemy_definitions);
+
+    let hit_flash_material = materials.add(StandardMaterial {
+        base_color: Color::WHITE,
+        emissive: Color::WHITE,
+        unlit: true,
+        ..default()
+    });
+
+    let boss_hazard_mesh = meshes.add(
+        shape::Cylinder {
+            radius: 1.0,
+            height: 0.1,
+            ..default()
+        }
+        .into(),
+    );
+    let boss_hazard_material = materials.add(StandardMaterial {
+        base_color: Color::rgba(1.0, 0.2, 0.0, 0.4),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+
+    commands.insert_resource(EnemyResources {
+        hit_flash_material,
+        boss_hazard_mesh,
+        boss_hazard_material,
+    });
+}
+
+pub fn spawn_enemy(
+    enemy_definitions: &EnemyDefinitions,
+    weapons_assets: &WeaponAssets,
+    enemy_type: EnemyType,
+    difficulty: &DifficultyStep,
+    commands: &mut Commands,
+    transform: Transform,
+) -> Option<Entity> {
+    let Some(definition) = enemy_definitions.get(enemy_type) else {
+        warn!("No definition for enemy '{}'", enemy_type.0);
+        return None;
+    };
+
+    let weapon = definition.weapon.map(|weapon| {
+        let weapon_transform = Transform::from_translation(weapon.offset);
+        let weapon_entity = match weapon.weapon_type {
+            WeaponType::Pistol => attach_weapon!(
+                commands,
+                weapons_assets,
+                weapon_transform,
+                pistol,
+                pistol_scene
+            )
+            .insert(EnemyWeapon)
+            .id(),
+            WeaponType::Shotgun => attach_weapon!(
+                commands,
+                weapons_assets,
+                weapon_transform,
+                shotgun,
+                shotgun_scene
+            )
+            .insert(EnemyWeapon)
+            .id(),
+            WeaponType::Minigun => attach_weapon!(
+                commands,
+                weapons_assets,
+                weapon_transform,
+                minigun,
+                minigun_scene
+            )
+            .insert(EnemyWeapon)
+            .id(),
+        };
+        commands.entity(weapon_entity).insert((
+            WeaponAttackTimer::new(weapon.weapon_type.attack_speed() / difficulty.enemy_fire_rate),
+            Ammo {
+                ammo: weapon.weapon_type.max_ammo(difficulty.ammo),
+            },
+        ));
+        weapon_entity
+    });
+
+    let enemy = Enemy {
+        enemy_type,
+        behavior: definition.behavior,
+        minion: definition.minion,
+        squad: definition.squad,
+        speed: definition.speed * difficulty.enemy_speed,
+        rotation_speed: definition.rotation_speed,
+        min_distance: definition.min_distance,
+        weapon_type: definition.weapon.map(|weapon| weapon.weapon_type),
+        attached_weapon: weapon,
+    };
+    let health = (definition.health as f32 * difficulty.enemy_health) as i32;
+    let mut enemy_commands = commands.spawn(EnemyBundle {
+        scene_bundle: SceneBundle {
+            scene: definition.scene.clone(),
+            transform: transform.with_scale(Vec3::splat(definition.scale)),
+            ..default()
+        },
+        enemy,
+        brain: EnemyBrain::new(transform.translation.xy(), health),
+        aim: definition
+            .weapon
+            .map(|weapon| EnemyAim::new(&weapon.aim, difficulty.enemy_accuracy))
+            .unwrap_or_default(),
+        health: Health { health },
+        resistances: definition.resistances,
+        collider: Collider::cuboid(
+            definition.collider.x,
+            definition.collider.y,
+            definition.collider.z,
+        ),
+        ..default()
+    });
+    if let Some(weapon) = weapon {
+        enemy_commands.add_child(weapon);
+    }
+    // definitions of spawners and bosses always have a minion
+    let minion = definition.minion.unwrap_or(enemy_type);
+    if definition.behavior == EnemyBehavior::Spawner {
+        enemy_commands.insert(EnemySpawner::new(*difficulty, definition.scale, minion));
+    }
+    let enemy_entity = enemy_commands.id();
+
+    if definition.behavior == EnemyBehavior::Boss {
+        let shotgun_transform = Transform::from_translation(ENEMY_BOSS_SECOND_WEAPON_OFFSET);
+        let shotgun = attach_weapon!(
+            commands,
+            weapons_assets,
+            shotgun_transform,
+            shotgun,
+            shotgun_scene
+        )
+        .insert(EnemyWeapon)
+        .id();
+        commands
+            .entity(enemy_entity)
+            .add_child(shotgun)
+            .insert(Boss::new(*difficulty, health, shotgun, minion));
+    }
+    Some(enemy_entity)
+}
+
+fn enemy_enable(
+    enemies: Query<Entity, With<DisabledEnemy>>,
+    mut commands: Commands,
+    mut level_started_events: EventReader<LevelStarted>,
+) {
+    for _ in level_started_events.read() {
+        for enemy in enemies.iter() {
+            commands
+                .get_entity(enemy)
+                .unwrap()
+                .remove::<DisabledEnemy>();
+        }
+    }
+}
+
+fn enemy_update_path(
+    time: Res<Time>,
+    nav_grid: Option<ResMut<NavGrid>>,
+    mut enemies: Query<
+        (
+            &Transform,
+            &EnemyBrain,
+            Option<&SquadMember>,
+            &mut EnemyPath,
+        ),
+        Without<DisabledEnemy>,
+    >,
+) {
+    let Some(mut nav_grid) = nav_grid else {
+        return;
+    };
+
+    for (enemy_transform, enemy_brain, squad_member, mut enemy_path) in enemies.iter_mut() {
+        let Some(target) = squad_move_target(enemy_brain, squad_member) else {
+            enemy_path.waypoints.clear();
+            continue;
+        };
+
+        enemy_path.repath_timer.tick(time.delta());
+        if !enemy_path.repath_timer.just_finished() && !enemy_path.waypoints.is_empty() {
+            continue;
+        }
+
+        let enemy_cell = nav_grid.world_to_cell(enemy_transform.translation.xy());
+        let target_cell = nav_grid.world_to_cell(target);
+        enemy_path.waypoints = match (enemy_cell, target_cell) {
+            (Some(enemy_cell), Some(target_cell)) => nav_grid
+                .find_path(enemy_cell, target_cell)
+                .unwrap_or_default(),
+            _ => vec![],
+        };
+    }
+}
+
+#[allow(clippy::complexity)]
+fn enemy_move(
+    time: Res<Time>,
+    player: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    mut enemy_steering: ResMut<EnemySteering>,
+    mut enemies: Query<
+        (
+            Entity,
+            &Enemy,
+            &mut EnemyBrain,
+            &mut EnemyPath,
+            Option<&SquadMember>,
+            &EnemyHitReaction,
+            &mut Transform,
+            &mut KinematicCharacterController,
+        ),
+        (Without<DisabledEnemy>, Without<Player>),
+    >,
+) {
+    let Ok(player_transfomr) = player.get_single() else {
+        return;
+    };
+
+    enemy_steering.update(
+        enemies
+            .iter()
+            .map(|(entity, _, _, _, _, _, transform, _)| (entity, transform.translation.xy())),
+    );
+
+    for (
+        enemy_entity,
+        enemy,
+        mut enemy_brain,
+        mut enemy_path,
+        squad_member,
+        enemy_hit_reaction,
+        mut enemy_transform,
+        mut enemy_controller,
+    ) in enemies.iter_mut()
+    {
+        let enemy_position = enemy_transform.translation.xy();
+        while let Some(waypoint) = enemy_path.waypoints.first() {
+            if enemy_position.distance(*waypoint) < ENEMY_WAYPOINT_DISTANCE {
+                enemy_path.waypoints.remove(0);
+            } else {
+                break;
+            }
+        }
+
+        if enemy_brain.state == EnemyState::Patrol
+            && enemy_position.distance(enemy_brain.patrol_point()) < ENEMY_WAYPOINT_DISTANCE
+        {
+            enemy_brain.next_patrol_point();
+        }
+
+        let to_player = player_transfomr.translation.xy() - enemy_position;
+        let move_direction = match enemy_brain.state {
+            // flinching enemies only get knocked back
+            _ if enemy_hit_reaction.is_flinching() => None,
+            EnemyState::Idle => None,
+            EnemyState::Strafe => Some(to_player.perp().normalize() * enemy_brain.strafe_sign()),
+            EnemyState::Retreat => Some(-to_player.normalize()),
+            EnemyState::Chase
+                if to_player.length_squared()
+                    <= squad_min_distance(enemy.min_distance, squad_member) =>
+            {
+                None
+            }
+            EnemyState::Patrol | EnemyState::Alert | EnemyState::Chase => {
+                // without a path go straight to the target
+                let target = enemy_path
+                    .waypoints
+                    .first()
+                    .copied()
+                    .or(squad_move_target(&enemy_brain, squad_member));
+                target
+                    .filter(|target| ENEMY_WAYPOINT_DISTANCE < enemy_position.distance(*target))
+                    .map(|target| (target - enemy_position).normalize())
+            }
+        };
+        let mut movement = enemy_hit_reaction.knockback() * time.delta_seconds();
+        if let Some(move_direction) = move_direction {
+            movement += move_direction * enemy.speed * time.delta_seconds();
+        }
+        // enemies keep distance from each other even when standing
+        movement += enemy_steering.separation(enemy_entity, enemy_position)
+            * enemy.speed
+            * STEERING_SEPARATION_WEIGHT
+            * time.delta_seconds();
+        if movement != Vec2::ZERO {
+            enemy_controller.translation = Some(movement.extend(0.0));
+        }
+
+        // engaged enemies look at the player, others
+        // look where they go
+        let look_direction = if enemy_brain.state.is_engaged() {
+            Some(to_player.normalize())
+        } else {
+            move_direction
+        };
+        let Some(look_direction) = look_direction else {
+            continue;
+        };
+
+        let direction = look_direction.extend(0.0);
+        let enemy_forward = enemy_transform.rotation * Vec3::Y;
+        let mut angle = direction.angle_between(enemy_forward);
+        let cross = direction.cross(enemy_forward);
+        if 0.0 <= cross.z {
+            angle *= -1.0;
+        }
+        let target_rotation = enemy_transform.rotation * Quat::from_rotation_z(angle);
+        enemy_transform.rotation = enemy_transform
+            .rotation
+            .lerp(target_rotation, enemy.rotation_speed * time.delta_seconds());
+    }
+}
+
+fn spawn_parts(
+    definition: &EnemyDefinition,
+    part_mesh: Handle<Mesh>,
+    part_material: Handle<StandardMaterial>,
+    enemy_transform: Transform,
+    commands: &mut Commands,
+) {
+    let dimention = definition.dimention();
+    let part_dimention = definition.part_dimention();
+    let gap = definition.death_gap;
+    let gap_delta = definition.death_gap_delta();
+    for x in 0..definition.parts.x {
+        for y in 0..definition.parts.y {
+            for z in 0..definition.parts.z {
+                let x_pos =
+                    -(dimention.x + gap.x) / 2.0 + (part_dimention.x + gap_delta.x) * x as f32;
+                let y_pos =
+                    -(dimention.y + gap.y) / 2.0 + (part_dimention.y + gap_delta.y) * y as f32;
+                let z_pos = -(dimention.z + gap.z) / 2.0
+                            + (part_dimention.z + gap_delta.z) * z as f32
+                            // to make all parts be above ground
+                            + dimention.z / 2.0;
+                let pos = Vec3::new(x_pos, y_pos, z_pos);
+                let translation = enemy_transform.transform_point(pos);
+                let transform = Transform::from_translation(translation)
+                    .with_rotation(enemy_transform.rotation);
+                let linvel = (translation - enemy_transform.translation).normalize()
+                    * definition.death_pulse;
+                commands.spawn((
+                    PbrBundle {
+                        mesh: part_mesh.clone(),
+                        material: part_material.clone(),
+                        transform,
+                        ..default()
+                    },
+                    Collider::cuboid(
+                        part_dimention.x / 2.0,
+                        part_dimention.y / 2.0,
+                        part_dimention.z / 2.0,
+                    ),
+                    RigidBody::Dynamic,
+                    Velocity {
+                        linvel,
+                        ..default()
+                    },
+                    LevelObject,
+                ));
+            }
+        }
+    }
+}
+
+fn enemy_die(
+    room_difficulty: RoomDifficulty,
+    enemy_definitions: Res<EnemyDefinitions>,
+    pickup_resources: Res<PickupResources>,
+    enemies: Query<(Entity, &Transform, &Enemy), Without<EnemyWeapon>>,
+    mut combat_rng: ResMut<CombatRng>,
+    mut commands: Commands,
+    mut kill_events: EventReader<KillEvent>,
+) {
+    let difficulty = room_difficulty.step();
+    for kill_event in kill_events.read() {
+        if let Ok((enemy_entity, enemy_transform, enemy)) = enemies.get(kill_event.entity) {
+            let definition = enemy_definitions.get(enemy.enemy_type);
+            if let (Some(definition), Some(parts)) =
+                (definition, enemy_definitions.parts(enemy.enemy_type))
+            {
+                spawn_parts(
+                    definition,
+                    parts.mesh.clone(),
+                    parts.material.clone(),
+                    *enemy_transform,
+                    &mut commands,
+                );
+            }
+
+            let loot = definition.map_or(Loot::Nothing, |definition| {
+                definition.loot.roll(
+                    &difficulty,
+                    enemy.attached_weapon.is_some(),
+                    &mut combat_rng.0,
+                )
+            });
+            let pickup_type = match loot {
+                Loot::Nothing => None,
+                Loot::Weapon => {
+                    // weapon that is not dropped is despawned with the enemy
+                    if let (Some(attached_weapon), Some(weapon_type)) =
+                        (enemy.attached_weapon, enemy.weapon_type)
+                    {
+                        commands
+                            .get_entity(enemy_entity)
+                            .unwrap()
+                            .remove_children(&[attached_weapon]);
+
+                        // enemy fire rate does not carry over to the player
+                        commands
+                            .get_entity(attached_weapon)
+                            .unwrap()
+                            .remove::<EnemyWeapon>()
+                            .insert(WeaponAttackTimer::new(weapon_type.attack_speed()));
+
+                        commands
+                            .spawn(FloatingObjectBundle::new(enemy_transform.translation))
+                            .add_child(attached_weapon);
+                    }
+                    None
+                }
+                Loot::Ammo => Some(PickupType::Ammo),
+                Loot::Health => Some(PickupType::Health),
+                Loot::PowerUp => Some(PickupType::PowerUp),
+            };
+            if let Some(pickup_type) = pickup_type {
+                spawn_pickup(
+                    &pickup_resources,
+                    pickup_type,
+                    enemy_transform.translation,
+                    &mut commands,
+                );
+            }
+
+            commands
+                .get_entity(enemy_entity)
+                .unwrap()
+                .despawn_recursive();
+        }
+    }
+}
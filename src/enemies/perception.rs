@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{player::Player, weapons::ShootEvent, COLLISION_GROUP_ENEMY, COLLISION_GROUP_LEVEL};
+
+use super::{DisabledEnemy, EnemyWeapon};
+
+const PERCEPTION_VIEW_DISTANCE: f32 = 150.0;
+// Half of the view cone angle
+const PERCEPTION_VIEW_ANGLE: f32 = std::f32::consts::FRAC_PI_3;
+const PERCEPTION_HEARING_DISTANCE: f32 = 100.0;
+// Height of enemy eyes above its origin
+const PERCEPTION_EYES_HEIGHT: f32 = 2.0;
+
+// What enemy saw and heard in this frame
+#[derive(Default, Component)]
+pub struct EnemyPerception {
+    pub sees_player: bool,
+    // Closest player gunshot
+    pub heard_noise: Option<Vec2>,
+}
+
+pub fn enemy_perception(
+    rapier_context: Res<RapierContext>,
+    player: Query<&Transform, With<Player>>,
+    enemy_weapons: Query<(), With<EnemyWeapon>>,
+    mut enemies: Query<(&Transform, &mut EnemyPerception), Without<DisabledEnemy>>,
+    mut shoot_events: EventReader<ShootEvent>,
+) {
+    // only shots of the player make noise
+    let noises = shoot_events
+        .read()
+        .filter(|e| enemy_weapons.get(e.weapon_entity).is_err())
+        .map(|e| e.weapon_translation.xy())
+        .collect::<Vec<_>>();
+
+    let Ok(player_transform) = player.get_single() else {
+        return;
+    };
+
+    for (enemy_transform, mut enemy_perception) in enemies.iter_mut() {
+        let enemy_position = enemy_transform.translation.xy();
+        let to_player = player_transform.translation.xy() - enemy_position;
+        let enemy_forward = (enemy_transform.rotation * Vec3::Y).xy();
+
+        let in_view = to_player.length() < PERCEPTION_VIEW_DISTANCE
+            && enemy_forward.angle_between(to_player).abs() < PERCEPTION_VIEW_ANGLE;
+
+        // player is visible if nothing in the level blocks the view
+        enemy_perception.sees_player = in_view && {
+            let ray_origin = enemy_transform.translation + Vec3::Z * PERCEPTION_EYES_HEIGHT;
+            let ray = player_transform.translation - ray_origin;
+            let filter = QueryFilter {
+                flags: QueryFilterFlags::EXCLUDE_SENSORS,
+                groups: Some(CollisionGroups::new(
+                    COLLISION_GROUP_ENEMY,
+                    COLLISION_GROUP_LEVEL,
+                )),
+                ..default()
+            };
+            rapier_context
+                .cast_ray(ray_origin, ray.normalize(), ray.length(), true, filter)
+                .is_none()
+        };
+
+        enemy_perception.heard_noise = noises
+            .iter()
+            .filter(|noise| noise.distance(enemy_position) < PERCEPTION_HEARING_DISTANCE)
+            .min_by(|a, b| {
+                a.distance_squared(enemy_position)
+                    .total_cmp(&b.distance_squared(enemy_position))
+            })
+            .copied();
+    }
+}
@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+
+use crate::{difficulty::DifficultyStep, player::Player, weapons::WeaponAssets};
+
+use super::{spawn_enemy, DisabledEnemy, Enemy, EnemyDefinitions, EnemyType};
+
+const SPAWNER_SPAWN_TIME: f32 = 4.0;
+// Time spawner stays open before minion comes out
+const SPAWNER_OPEN_TIME: f32 = 0.5;
+const SPAWNER_MAX_MINIONS: usize = 4;
+// Minions appear this far from the spawner towards the player
+const SPAWNER_SPAWN_OFFSET: f32 = 6.0;
+const SPAWNER_OPEN_SCALE: f32 = 1.2;
+
+#[derive(Component)]
+pub struct EnemySpawner {
+    // Minions are spawned with the same difficulty as the spawner
+    difficulty: DifficultyStep,
+    // Scale of the spawner when it is closed
+    scale: f32,
+    minion: EnemyType,
+    spawn_timer: Timer,
+    open_timer: Option<Timer>,
+    minions: Vec<Entity>,
+}
+
+impl EnemySpawner {
+    pub fn new(difficulty: DifficultyStep, scale: f32, minion: EnemyType) -> Self {
+        Self {
+            difficulty,
+            scale,
+            minion,
+            spawn_timer: Timer::from_seconds(SPAWNER_SPAWN_TIME, TimerMode::Repeating),
+            open_timer: None,
+            minions: vec![],
+        }
+    }
+}
+
+// Minions are normal `Enemy` entities, so the room is finished
+// only after the spawner and all its minions are dead.
+pub fn enemy_spawner_update(
+    time: Res<Time>,
+    enemy_definitions: Res<EnemyDefinitions>,
+    weapon_assets: Res<WeaponAssets>,
+    player: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    enemies: Query<(), With<Enemy>>,
+    mut spawners: Query<(&mut Transform, &mut EnemySpawner), Without<DisabledEnemy>>,
+    mut commands: Commands,
+) {
+    let Ok(player_transform) = player.get_single() else {
+        return;
+    };
+
+    for (mut spawner_transform, mut spawner) in spawners.iter_mut() {
+        spawner.minions.retain(|minion| enemies.contains(*minion));
+
+        let Some(open_timer) = spawner.open_timer.as_mut() else {
+            spawner.spawn_timer.tick(time.delta());
+            if spawner.spawn_timer.just_finished() && spawner.minions.len() < SPAWNER_MAX_MINIONS {
+                spawner.open_timer = Some(Timer::from_seconds(SPAWNER_OPEN_TIME, TimerMode::Once));
+            }
+            continue;
+        };
+
+        // spawner swells while it is open
+        open_timer.tick(time.delta());
+        let open = (open_timer.percent() * std::f32::consts::PI).sin();
+        let open_finished = open_timer.finished();
+        spawner_transform.scale =
+            Vec3::splat(spawner.scale * (1.0 + (SPAWNER_OPEN_SCALE - 1.0) * open));
+        if !open_finished {
+            continue;
+        }
+        spawner.open_timer = None;
+        spawner_transform.scale = Vec3::splat(spawner.scale);
+
+        let to_player = (player_transform.translation.xy() - spawner_transform.translation.xy())
+            .normalize_or_zero();
+        let minion_translation =
+            spawner_transform.translation + (to_player * SPAWNER_SPAWN_OFFSET).extend(0.0);
+        let Some(minion) = spawn_enemy(
+            &enemy_definitions,
+            &weapon_assets,
+            spawner.minion,
+            &spawner.difficulty,
+            &mut commands,
+            Transform::from_translation(minion_translation),
+        ) else {
+            continue;
+        };
+        // the room has already started, so `enemy_enable`
+        // will not enable the minion
+        commands.entity(minion).remove::<DisabledEnemy>();
+        spawner.minions.push(minion);
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{damage::KillEvent, player::Player};
+
+use super::{
+    brain::{EnemyBrain, EnemyState},
+    DisabledEnemy, Enemy,
+};
+
+// Half of the arc rushers and suppressors are spread over
+const SQUAD_RUSHER_SPREAD: f32 = std::f32::consts::FRAC_PI_6;
+const SQUAD_SUPPRESSOR_SPREAD: f32 = std::f32::consts::FRAC_PI_4;
+// Flankers go to the sides of the player, each next
+// pair of flankers goes a bit further around
+const SQUAD_FLANKER_ANGLE: f32 = std::f32::consts::FRAC_PI_2;
+const SQUAD_FLANKER_STEP: f32 = std::f32::consts::FRAC_PI_8;
+
+// Distances to the player are multipliers of the enemy min distance
+const SQUAD_RUSHER_DISTANCE: f32 = 0.5;
+const SQUAD_FLANKER_DISTANCE: f32 = 0.9;
+const SQUAD_SUPPRESSOR_DISTANCE: f32 = 1.5;
+// Rushers only stop when they reach their approach point.
+// Min distance is squared, so the multiplier is too.
+const SQUAD_RUSHER_MIN_DISTANCE: f32 = SQUAD_RUSHER_DISTANCE * SQUAD_RUSHER_DISTANCE;
+
+// Squad mates closer than this to the line between
+// a shooter and the player are in the line of fire
+const SQUAD_LANE_WIDTH: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SquadRole {
+    // Keeps distance and shoots
+    Suppressor,
+    // Comes from the sides of the player
+    Flanker,
+    // Goes straight at the player
+    Rusher,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+pub struct SquadMember {
+    pub role: SquadRole,
+    // Direction from the player to the spot
+    // member approaches the player from
+    pub angle: f32,
+    // Point member walks to while chasing the player
+    pub approach: Option<Vec2>,
+    // Squad mate is between the member and the player
+    pub lane_blocked: bool,
+}
+
+// Role enemy gets in a squad, set by its definition
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum SquadPlacement {
+    // Role follows the distance to the player
+    #[default]
+    Any,
+    Rusher,
+    Suppressor,
+    // Enemy does not join squads
+    Outside,
+}
+
+// Enemy that takes part in the squad assignment
+#[derive(Debug, Clone, Copy)]
+pub struct SquadCandidate {
+    pub entity: Entity,
+    pub placement: SquadPlacement,
+    pub position: Vec2,
+}
+
+// Spreads `count` angles evenly over the `-arc..=arc`
+fn spread_angle(index: usize, count: usize, arc: f32) -> f32 {
+    if count <= 1 {
+        0.0
+    } else {
+        -arc + 2.0 * arc * index as f32 / (count - 1) as f32
+    }
+}
+
+// Closest third of the squad rushes, furthest third suppresses
+// and the rest flanks. Enemies placed by their definition always
+// get their role. Members of the same role keep their order
+// around the player, so they do not cross paths.
+pub fn assign_squad_roles(
+    candidates: &[SquadCandidate],
+    player_position: Vec2,
+) -> Vec<(Entity, SquadMember)> {
+    if candidates.is_empty() {
+        return vec![];
+    }
+
+    let center = candidates
+        .iter()
+        .map(|candidate| candidate.position)
+        .sum::<Vec2>()
+        / candidates.len() as f32;
+    let front = (center - player_position)
+        .try_normalize()
+        .unwrap_or(Vec2::Y);
+    let relative_angle =
+        |candidate: &SquadCandidate| front.angle_between(candidate.position - player_position);
+
+    let mut flexible = candidates
+        .iter()
+        .filter(|candidate| candidate.placement == SquadPlacement::Any)
+        .collect::<Vec<_>>();
+    flexible.sort_by(|a, b| {
+        a.position
+            .distance_squared(player_position)
+            .total_cmp(&b.position.distance_squared(player_position))
+    });
+
+    let mut groups: [Vec<&SquadCandidate>; 3] = [vec![], vec![], vec![]];
+    for (i, candidate) in flexible.iter().enumerate() {
+        groups[i * 3 / flexible.len()].push(candidate);
+    }
+    for candidate in candidates.iter() {
+        match candidate.placement {
+            SquadPlacement::Rusher => groups[0].push(candidate),
+            SquadPlacement::Suppressor => groups[2].push(candidate),
+            SquadPlacement::Any | SquadPlacement::Outside => {}
+        }
+    }
+
+    let front_angle = Vec2::Y.angle_between(front);
+    let mut members = vec![];
+    for (group, role) in
+        groups
+            .iter_mut()
+            .zip([SquadRole::Rusher, SquadRole::Flanker, SquadRole::Suppressor])
+    {
+        group.sort_by(|a, b| relative_angle(a).total_cmp(&relative_angle(b)));
+        let count = group.len();
+        for (i, candidate) in group.iter().enumerate() {
+            let angle = match role {
+                SquadRole::Rusher => spread_angle(i, count, SQUAD_RUSHER_SPREAD),
+                SquadRole::Suppressor => spread_angle(i, count, SQUAD_SUPPRESSOR_SPREAD),
+                SquadRole::Flanker => {
+                    let side = if i % 2 == 0 { -1.0 } else { 1.0 };
+                    side * (SQUAD_FLANKER_ANGLE + (i / 2) as f32 * SQUAD_FLANKER_STEP)
+                }
+            };
+            members.push((
+                candidate.entity,
+                SquadMember {
+                    role,
+                    angle: front_angle + angle,
+                    approach: None,
+                    lane_blocked: false,
+                },
+            ));
+        }
+    }
+    members
+}
+
+// Position is in the line of fire if it is
+// between the shooter and the target
+pub fn in_lane(shooter: Vec2, target: Vec2, position: Vec2) -> bool {
+    let lane = target - shooter;
+    let length_squared = lane.length_squared();
+    if length_squared == 0.0 {
+        return false;
+    }
+    let t = (position - shooter).dot(lane) / length_squared;
+    if t <= 0.0 || 1.0 <= t {
+        return false;
+    }
+    (shooter + lane * t).distance(position) < SQUAD_LANE_WIDTH
+}
+
+// Position member walks to in the current state
+pub fn squad_move_target(brain: &EnemyBrain, member: Option<&SquadMember>) -> Option<Vec2> {
+    match (brain.state, member.and_then(|member| member.approach)) {
+        (EnemyState::Chase, Some(approach)) => Some(approach),
+        _ => brain.move_target(),
+    }
+}
+
+// Squared distance to the player member stops chasing at
+pub fn squad_min_distance(min_distance: f32, member: Option<&SquadMember>) -> f32 {
+    match member.map(|member| member.role) {
+        Some(SquadRole::Rusher) => min_distance * SQUAD_RUSHER_MIN_DISTANCE,
+        _ => min_distance,
+    }
+}
+
+// Roles are assigned again when somebody joins
+// or leaves the squad
+pub fn squad_assign(
+    player: Query<&Transform, With<Player>>,
+    enemies: Query<(Entity, &Enemy, &Transform, Option<&SquadMember>), Without<DisabledEnemy>>,
+    mut commands: Commands,
+    mut kill_events: EventReader<KillEvent>,
+) {
+    let killed = kill_events
+        .read()
+        .filter(|e| matches!(enemies.get(e.entity), Ok((_, _, _, Some(_)))))
+        .map(|e| e.entity)
+        .collect::<HashSet<_>>();
+
+    let Ok(player_transform) = player.get_single() else {
+        return;
+    };
+
+    let candidates = enemies
+        .iter()
+        .filter(|(entity, enemy, _, _)| {
+            enemy.squad != SquadPlacement::Outside && !killed.contains(entity)
+        })
+        .collect::<Vec<_>>();
+    let joined = candidates.iter().any(|(_, _, _, member)| member.is_none());
+    if killed.is_empty() && !joined {
+        return;
+    }
+
+    let candidates = candidates
+        .into_iter()
+        .map(|(entity, enemy, transform, _)| SquadCandidate {
+            entity,
+            placement: enemy.squad,
+            position: transform.translation.xy(),
+        })
+        .collect::<Vec<_>>();
+    for (entity, member) in assign_squad_roles(&candidates, player_transform.translation.xy()) {
+        commands.entity(entity).insert(member);
+    }
+}
+
+// Updates approach points around the player and
+// keeps members out of each other line of fire
+pub fn squad_update(
+    player: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    mut members: Query<(Entity, &Enemy, &Transform, &EnemyBrain, &mut SquadMember)>,
+) {
+    let Ok(player_transform) = player.get_single() else {
+        return;
+    };
+    let player_position = player_transform.translation.xy();
+
+    let positions = members
+        .iter()
+        .map(|(entity, enemy, transform, _, _)| {
+            (
+                entity,
+                transform.translation.xy(),
+                enemy.weapon_type.is_some(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    for (entity, enemy, transform, brain, mut member) in members.iter_mut() {
+        let position = transform.translation.xy();
+
+        member.lane_blocked = enemy.weapon_type.is_some()
+            && positions.iter().any(|(other, other_position, _)| {
+                *other != entity && in_lane(position, player_position, *other_position)
+            });
+
+        if brain.state != EnemyState::Chase {
+            member.approach = None;
+            continue;
+        }
+
+        let distance = enemy.min_distance.sqrt()
+            * match member.role {
+                SquadRole::Rusher => SQUAD_RUSHER_DISTANCE,
+                SquadRole::Flanker => SQUAD_FLANKER_DISTANCE,
+                SquadRole::Suppressor => SQUAD_SUPPRESSOR_DISTANCE,
+            };
+        let mut approach =
+            player_position + Vec2::from_angle(member.angle).rotate(Vec2::Y) * distance;
+
+        // step aside from the lanes of armed squad mates
+        for (other, other_position, armed) in positions.iter() {
+            if *other == entity || !armed || !in_lane(*other_position, player_position, position) {
+                continue;
+            }
+            let lane = (player_position - *other_position).normalize_or_zero();
+            let side = lane.perp_dot(position - *other_position).signum();
+            approach += lane.perp() * side * SQUAD_LANE_WIDTH * 2.0;
+        }
+        member.approach = Some(approach);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(index: u32, placement: SquadPlacement, position: Vec2) -> SquadCandidate {
+        SquadCandidate {
+            entity: Entity::from_raw(index),
+            placement,
+            position,
+        }
+    }
+
+    fn role(members: &[(Entity, SquadMember)], index: u32) -> SquadRole {
+        members
+            .iter()
+            .find(|(entity, _)| *entity == Entity::from_raw(index))
+            .unwrap()
+            .1
+            .role
+    }
+
+    #[test]
+    fn no_candidates_no_squad() {
+        assert!(assign_squad_roles(&[], Vec2::ZERO).is_empty());
+    }
+
+    #[test]
+    fn roles_follow_distance() {
+        let candidates = (0..6)
+            .map(|i| candidate(i, SquadPlacement::Any, Vec2::Y * 10.0 * (i + 1) as f32))
+            .collect::<Vec<_>>();
+        let members = assign_squad_roles(&candidates, Vec2::ZERO);
+
+        assert_eq!(members.len(), candidates.len());
+        for (index, expected) in [
+            SquadRole::Rusher,
+            SquadRole::Rusher,
+            SquadRole::Flanker,
+            SquadRole::Flanker,
+            SquadRole::Suppressor,
+            SquadRole::Suppressor,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            assert_eq!(role(&members, index as u32), expected);
+        }
+    }
+
+    #[test]
+    fn placed_enemies_keep_their_role() {
+        let candidates = [
+            candidate(0, SquadPlacement::Suppressor, Vec2::Y * 10.0),
+            candidate(1, SquadPlacement::Any, Vec2::Y * 20.0),
+            candidate(2, SquadPlacement::Rusher, Vec2::Y * 30.0),
+        ];
+        let members = assign_squad_roles(&candidates, Vec2::ZERO);
+
+        assert_eq!(role(&members, 0), SquadRole::Suppressor);
+        assert_eq!(role(&members, 2), SquadRole::Rusher);
+    }
+
+    #[test]
+    fn flankers_go_to_both_sides() {
+        let candidates = (0..6)
+            .map(|i| candidate(i, SquadPlacement::Any, Vec2::Y * 10.0 * (i + 1) as f32))
+            .collect::<Vec<_>>();
+        let members = assign_squad_roles(&candidates, Vec2::ZERO);
+
+        let flanker_angles = members
+            .iter()
+            .filter(|(_, member)| member.role == SquadRole::Flanker)
+            .map(|(_, member)| member.angle)
+            .collect::<Vec<_>>();
+        assert_eq!(flanker_angles.len(), 2);
+        assert!(flanker_angles[0] * flanker_angles[1] < 0.0);
+        for angle in flanker_angles {
+            assert!((angle.abs() - SQUAD_FLANKER_ANGLE).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn lane_is_between_shooter_and_target() {
+        let shooter = Vec2::ZERO;
+        let target = Vec2::Y * 100.0;
+
+        assert!(in_lane(shooter, target, Vec2::new(1.0, 50.0)));
+        assert!(!in_lane(
+            shooter,
+            target,
+            Vec2::new(SQUAD_LANE_WIDTH * 2.0, 50.0)
+        ));
+        // behind the shooter and behind the target
+        assert!(!in_lane(shooter, target, Vec2::Y * -10.0));
+        assert!(!in_lane(shooter, target, Vec2::Y * 110.0));
+        // shooter standing on the target has no lane
+        assert!(!in_lane(shooter, shooter, shooter));
+    }
+
+    #[test]
+    fn rushers_get_closer() {
+        let member = |role| SquadMember {
+            role,
+            angle: 0.0,
+            approach: None,
+            lane_blocked: false,
+        };
+        let min_distance = 400.0;
+        let rusher = squad_min_distance(min_distance, Some(&member(SquadRole::Rusher)));
+        // rushers stop at their approach point
+        assert!((rusher.sqrt() - min_distance.sqrt() * SQUAD_RUSHER_DISTANCE).abs() < 1e-3);
+        assert_eq!(
+            squad_min_distance(min_distance, Some(&member(SquadRole::Flanker))),
+            min_distance
+        );
+        assert_eq!(squad_min_distance(min_distance, None), min_distance);
+    }
+}
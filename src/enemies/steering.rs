@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+// If set, enemies do not steer away from each other
+const STEERING_NO_SEPARATION_ENV: &str = "FRIDGES_NO_SEPARATION";
+
+// Enemies closer than this push each other away
+const STEERING_SEPARATION_RADIUS: f32 = 6.0;
+// Fraction of the enemy speed used to keep the distance
+pub const STEERING_SEPARATION_WEIGHT: f32 = 0.8;
+
+// Positions grouped by grid cells, so only
+// nearby cells are searched for neighbours
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(Entity, Vec2)>>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+
+    // Keeps cells used in the last frame for the next one. Cells
+    // nobody used are dropped, so the map does not grow while
+    // enemies move from room to room.
+    pub fn clear(&mut self) {
+        self.cells.retain(|_, entries| !entries.is_empty());
+        for entries in self.cells.values_mut() {
+            entries.clear();
+        }
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec2) {
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_default().push((entity, position));
+    }
+
+    // Entries in the cell of the `position` and the cells around it.
+    // Some of them can be further than `cell_size`.
+    pub fn neighbours(&self, position: Vec2) -> impl Iterator<Item = &(Entity, Vec2)> {
+        let cell = self.cell(position);
+        (-1..=1)
+            .flat_map(move |y| (-1..=1).map(move |x| cell + IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+    }
+
+    // Direction away from neighbours closer than `radius`. Closer
+    // neighbours push harder. Length is at most 1.
+    pub fn separation(&self, entity: Entity, position: Vec2, radius: f32) -> Vec2 {
+        let mut push = Vec2::ZERO;
+        for (other, other_position) in self.neighbours(position) {
+            if *other == entity {
+                continue;
+            }
+            let away = position - *other_position;
+            let distance = away.length();
+            if radius <= distance {
+                continue;
+            }
+            // fully overlapping enemies are pushed apart
+            // in a direction stable for the pair
+            let direction = away.try_normalize().unwrap_or_else(|| {
+                if entity < *other {
+                    Vec2::X
+                } else {
+                    Vec2::NEG_X
+                }
+            });
+            push += direction * (1.0 - distance / radius);
+        }
+        push.clamp_length_max(1.0)
+    }
+}
+
+#[derive(Resource)]
+pub struct EnemySteering {
+    pub separation: bool,
+    hash: SpatialHash,
+}
+
+impl EnemySteering {
+    pub fn from_env() -> Self {
+        Self {
+            separation: std::env::var(STEERING_NO_SEPARATION_ENV).is_err(),
+            hash: SpatialHash::new(STEERING_SEPARATION_RADIUS),
+        }
+    }
+
+    // Rebuilds the hash from the positions of all moving enemies
+    pub fn update(&mut self, positions: impl Iterator<Item = (Entity, Vec2)>) {
+        self.hash.clear();
+        if !self.separation {
+            return;
+        }
+        for (entity, position) in positions {
+            self.hash.insert(entity, position);
+        }
+    }
+
+    pub fn separation(&self, entity: Entity, position: Vec2) -> Vec2 {
+        if !self.separation {
+            return Vec2::ZERO;
+        }
+        self.hash
+            .separation(entity, position, STEERING_SEPARATION_RADIUS)
+    }
+}
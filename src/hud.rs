@@ -1,9 +1,11 @@
-use bevy::{core_pipeline::clear_color::ClearColorConfig, prelude::*};
+use bevy::{core_pipeline::clear_color::ClearColorConfig, prelude::*, sprite::Anchor};
 
 use crate::{
-    damage::DamageEvent,
+    damage::{ArmorBreakEvent, DamageEvent, Health, ShieldBreakEvent},
+    enemies::{Boss, BossPhase, BossPhaseEvent, DisabledEnemy, Elite},
     level::{LevelInfo, LevelStarted},
     player::{Player, PlayerCamera},
+    run::RunConfig,
     ui::UiAssets,
     GlobalState,
 };
@@ -24,6 +26,26 @@ const TUTORIAL_TEXT: &str =
 const TUTORIAL_TEXT_DISPAWN_TIME_SECONDS: f32 = 5.0;
 const BOSS_TEXT: &str = "THE RED DRAGON LAIR";
 const BOSS_TEXT_DISPAWN_TIME_SECONDS: f32 = 2.0;
+const BOSS_PHASE_TEXT_DISPAWN_TIME_SECONDS: f32 = 1.5;
+
+const BOSS_HEALTH_BAR_COLOR: Color = Color::ORANGE_RED;
+const BOSS_HEALTH_BAR_BACKGROUND_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.5);
+const BOSS_HEALTH_BAR_SIZE: Vec2 = Vec2::new(600.0, 16.0);
+const BOSS_HEALTH_BAR_TRANSLATION: Vec3 = Vec3::new(0.0, 300.0, 0.0);
+
+const ELITE_NAME_COLOR: Color = Color::GOLD;
+const ELITE_NAME_FONT_SIZE: f32 = 24.0;
+// Names are shown this high above elites
+const ELITE_NAME_HEIGHT: f32 = 5.0;
+
+const SHIELD_BREAK_TEXT: &str = "SHIELD BROKEN";
+const SHIELD_BREAK_COLOR: Color = Color::CYAN;
+const ARMOR_BREAK_TEXT: &str = "ARMOR BROKEN";
+const ARMOR_BREAK_COLOR: Color = Color::SILVER;
+const BREAK_FONT_SIZE: f32 = 20.0;
+// Texts are shown this high above the enemy
+const BREAK_HEIGHT: f32 = 3.0;
+const BREAK_DISPAWN_TIME_SECONDS: f32 = 0.8;
 
 pub struct HudPlugin;
 
@@ -45,6 +67,14 @@ impl Plugin for HudPlugin {
             (enable_hud, despawn_all_timed_elements, show_tutorial_text).chain(),
         );
 
+        app.add_systems(
+            OnTransition {
+                from: GlobalState::Editor,
+                to: GlobalState::InGame,
+            },
+            (enable_hud, despawn_all_timed_elements).chain(),
+        );
+
         app.add_systems(
             OnTransition {
                 from: GlobalState::InGame,
@@ -91,6 +121,10 @@ impl Plugin for HudPlugin {
                 display_incomming_damage,
                 progress_timed_elements,
                 show_boss_text,
+                show_boss_phase_text,
+                update_boss_health_bar,
+                update_elite_names,
+                show_break_texts,
             )
                 .run_if(in_state(GlobalState::InGame)),
         );
@@ -100,6 +134,17 @@ impl Plugin for HudPlugin {
 #[derive(Component)]
 struct HudCamera;
 
+#[derive(Component)]
+struct HudBossHealthBar;
+
+#[derive(Component)]
+struct HudBossHealthBarFill;
+
+#[derive(Component)]
+struct HudEliteName {
+    enemy: Entity,
+}
+
 #[derive(Component)]
 struct HudTimedElement {
     spawn_time: f32,
@@ -110,6 +155,9 @@ struct HudTimedElement {
 struct HudResources {
     text_style: TextStyle,
     boss_text_style: TextStyle,
+    elite_name_style: TextStyle,
+    shield_break_style: TextStyle,
+    armor_break_style: TextStyle,
 }
 
 fn init_hud(ui_assets: Res<UiAssets>, mut commands: Commands) {
@@ -178,6 +226,41 @@ fn init_hud(ui_assets: Res<UiAssets>, mut commands: Commands) {
         ..default()
     });
 
+    // Boss health bar
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: BOSS_HEALTH_BAR_BACKGROUND_COLOR,
+                    custom_size: Some(BOSS_HEALTH_BAR_SIZE),
+                    ..default()
+                },
+                transform: Transform::from_translation(BOSS_HEALTH_BAR_TRANSLATION),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            HudBossHealthBar,
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: BOSS_HEALTH_BAR_COLOR,
+                        custom_size: Some(BOSS_HEALTH_BAR_SIZE),
+                        anchor: Anchor::CenterLeft,
+                        ..default()
+                    },
+                    transform: Transform::from_translation(Vec3::new(
+                        -BOSS_HEALTH_BAR_SIZE.x / 2.0,
+                        0.0,
+                        0.1,
+                    )),
+                    ..default()
+                },
+                HudBossHealthBarFill,
+            ));
+        });
+
     commands.insert_resource(HudResources {
         text_style: TextStyle {
             font: ui_assets.font.clone(),
@@ -189,6 +272,21 @@ fn init_hud(ui_assets: Res<UiAssets>, mut commands: Commands) {
             font_size: 80.0,
             color: Color::ORANGE_RED,
         },
+        elite_name_style: TextStyle {
+            font: ui_assets.font.clone(),
+            font_size: ELITE_NAME_FONT_SIZE,
+            color: ELITE_NAME_COLOR,
+        },
+        shield_break_style: TextStyle {
+            font: ui_assets.font.clone(),
+            font_size: BREAK_FONT_SIZE,
+            color: SHIELD_BREAK_COLOR,
+        },
+        armor_break_style: TextStyle {
+            font: ui_assets.font.clone(),
+            font_size: BREAK_FONT_SIZE,
+            color: ARMOR_BREAK_COLOR,
+        },
     })
 }
 
@@ -226,11 +324,12 @@ fn show_boss_text(
     time: Res<Time>,
     hud_resources: Res<HudResources>,
     level_info: Res<LevelInfo>,
+    run_config: Res<RunConfig>,
     mut commands: Commands,
     mut level_started_events: EventReader<LevelStarted>,
 ) {
     for _ in level_started_events.read() {
-        if level_info.game_progress == 100 {
+        if run_config.is_boss_room(level_info.run_mode, level_info.room) {
             commands.spawn((
                 Text2dBundle {
                     text: Text::from_section(BOSS_TEXT, hud_resources.boss_text_style.clone())
@@ -246,6 +345,178 @@ fn show_boss_text(
     }
 }
 
+fn show_boss_phase_text(
+    time: Res<Time>,
+    hud_resources: Res<HudResources>,
+    mut commands: Commands,
+    mut boss_phase_events: EventReader<BossPhaseEvent>,
+) {
+    for event in boss_phase_events.read() {
+        let text = match event.phase {
+            BossPhase::Sweep => continue,
+            BossPhase::Volleys => "THE DRAGON IS ANGRY",
+            BossPhase::Summon => "THE DRAGON CALLS FOR HELP",
+            BossPhase::Hazards => "THE DRAGON IS FURIOUS",
+        };
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(text, hud_resources.boss_text_style.clone())
+                    .with_alignment(TextAlignment::Center),
+                ..default()
+            },
+            HudTimedElement {
+                spawn_time: time.elapsed_seconds(),
+                lifespawn: BOSS_PHASE_TEXT_DISPAWN_TIME_SECONDS,
+            },
+        ));
+    }
+}
+
+// Bar is shown while there is an active boss
+fn update_boss_health_bar(
+    bosses: Query<(&Health, &Boss), Without<DisabledEnemy>>,
+    mut health_bar: Query<&mut Visibility, With<HudBossHealthBar>>,
+    mut health_bar_fill: Query<&mut Sprite, With<HudBossHealthBarFill>>,
+) {
+    let Ok(mut health_bar_visibility) = health_bar.get_single_mut() else {
+        return;
+    };
+    let Ok(mut health_bar_fill_sprite) = health_bar_fill.get_single_mut() else {
+        return;
+    };
+
+    let Some((health, boss)) = bosses.iter().next() else {
+        *health_bar_visibility = Visibility::Hidden;
+        return;
+    };
+
+    *health_bar_visibility = Visibility::Inherited;
+    let fraction = (health.health as f32 / boss.max_health as f32).clamp(0.0, 1.0);
+    health_bar_fill_sprite.custom_size = Some(Vec2::new(
+        BOSS_HEALTH_BAR_SIZE.x * fraction,
+        BOSS_HEALTH_BAR_SIZE.y,
+    ));
+}
+
+// Position of the world point on the hud
+fn world_to_hud(
+    camera: &Camera,
+    camera_global_transform: &GlobalTransform,
+    translation: Vec3,
+) -> Option<Vec3> {
+    let viewport_size = camera.logical_viewport_size()?;
+    let position = camera.world_to_viewport(camera_global_transform, translation)?;
+    // viewport starts in the top left corner, hud is centered
+    Some(Vec3::new(
+        position.x - viewport_size.x / 2.0,
+        viewport_size.y / 2.0 - position.y,
+        0.0,
+    ))
+}
+
+// Names follow elites on the screen and
+// disappear when elites die
+fn update_elite_names(
+    hud_resources: Res<HudResources>,
+    elites: Query<(Entity, &GlobalTransform, &Elite), Without<DisabledEnemy>>,
+    player_camera: Query<(&Camera, &GlobalTransform), With<PlayerCamera>>,
+    mut names: Query<(Entity, &HudEliteName, &mut Transform, &mut Visibility)>,
+    mut commands: Commands,
+) {
+    let Ok((camera, camera_global_transform)) = player_camera.get_single() else {
+        return;
+    };
+
+    for (name_entity, name, mut name_transform, mut name_visibility) in names.iter_mut() {
+        let Ok((_, elite_global_transform, _)) = elites.get(name.enemy) else {
+            commands.entity(name_entity).despawn_recursive();
+            continue;
+        };
+        let name_translation = elite_global_transform.translation() + Vec3::Z * ELITE_NAME_HEIGHT;
+        match world_to_hud(camera, camera_global_transform, name_translation) {
+            Some(translation) => {
+                name_transform.translation = translation;
+                *name_visibility = Visibility::Inherited;
+            }
+            None => *name_visibility = Visibility::Hidden,
+        }
+    }
+
+    for (elite_entity, _, elite) in elites.iter() {
+        if names
+            .iter()
+            .any(|(_, name, _, _)| name.enemy == elite_entity)
+        {
+            continue;
+        }
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(elite.name(), hud_resources.elite_name_style.clone())
+                    .with_alignment(TextAlignment::Center),
+                // shown after the first position update
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            HudEliteName {
+                enemy: elite_entity,
+            },
+        ));
+    }
+}
+
+// Short texts above enemies which lost their shield or armor
+fn show_break_texts(
+    time: Res<Time>,
+    hud_resources: Res<HudResources>,
+    enemies: Query<&GlobalTransform>,
+    player_camera: Query<(&Camera, &GlobalTransform), With<PlayerCamera>>,
+    mut commands: Commands,
+    mut shield_break_events: EventReader<ShieldBreakEvent>,
+    mut armor_break_events: EventReader<ArmorBreakEvent>,
+) {
+    let Ok((camera, camera_global_transform)) = player_camera.get_single() else {
+        return;
+    };
+
+    let breaks = shield_break_events
+        .read()
+        .map(|e| {
+            (
+                e.entity,
+                SHIELD_BREAK_TEXT,
+                &hud_resources.shield_break_style,
+            )
+        })
+        .chain(
+            armor_break_events
+                .read()
+                .map(|e| (e.entity, ARMOR_BREAK_TEXT, &hud_resources.armor_break_style)),
+        );
+    for (entity, text, style) in breaks {
+        let Ok(enemy_global_transform) = enemies.get(entity) else {
+            continue;
+        };
+        let Some(translation) = world_to_hud(
+            camera,
+            camera_global_transform,
+            enemy_global_transform.translation() + Vec3::Z * BREAK_HEIGHT,
+        ) else {
+            continue;
+        };
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(text, style.clone()).with_alignment(TextAlignment::Center),
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+            HudTimedElement {
+                spawn_time: time.elapsed_seconds(),
+                lifespawn: BREAK_DISPAWN_TIME_SECONDS,
+            },
+        ));
+    }
+}
+
 fn display_incomming_damage(
     time: Res<Time>,
     player: Query<Entity, With<Player>>,
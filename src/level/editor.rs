@@ -0,0 +1,634 @@
+use bevy::{input::mouse::MouseWheel, prelude::*};
+
+use crate::{
+    difficulty::Difficulty,
+    enemies::{EnemyDefinitions, EnemyType},
+    run::RunSeed,
+    ui::UiAssets,
+    utils::remove_all_with,
+    weapons::WeaponType,
+    GlobalState,
+};
+
+use super::{
+    door::{Door, DoorState, DoorType},
+    generation::{CellType, LevelLayout},
+    room::parse_room,
+    spawn::{spawn_level, LevelSpawnResources},
+    LevelResources, LevelStarted, LevelType, RunSettings, COLUMN_SIZE, GRID_SIZE, LEVEL_SIZE,
+};
+
+const EDITOR_ROOM_PATH: &str = "assets/rooms/editor.room";
+
+const EDITOR_CAMERA_HEIGHT: f32 = 250.0;
+const EDITOR_CAMERA_MIN_HEIGHT: f32 = 50.0;
+const EDITOR_CAMERA_MAX_HEIGHT: f32 = 400.0;
+const EDITOR_CAMERA_SPEED: f32 = 1.0;
+const EDITOR_CAMERA_ZOOM_SPEED: f32 = 20.0;
+
+const EDITOR_CELL_SIZE: f32 = COLUMN_SIZE * 0.9;
+const EDITOR_CELL_HIGHT: f32 = 1.0;
+
+const EDITOR_HELP_TEXT: &str = "WASD - move, Scroll - zoom\n\
+    LMB - paint, RMB - erase\n\
+    1 - column, 2 - door, 3 - light\n\
+    4/5/6 - pistol/shotgun/minigun\n\
+    7 - enemy, press again for the next one\n\
+    0 - player\n\
+    F5 - save, F9 - load\n\
+    Enter - play test, Esc - main menu";
+
+const EDITOR_BRUSH_KEYS: [(KeyCode, Brush); 7] = [
+    (KeyCode::Key1, Brush::Column),
+    (KeyCode::Key2, Brush::Door),
+    (KeyCode::Key3, Brush::Light),
+    (KeyCode::Key4, Brush::Pistol),
+    (KeyCode::Key5, Brush::Shotgun),
+    (KeyCode::Key6, Brush::Minigun),
+    (KeyCode::Key0, Brush::Player),
+];
+// Cycles through the loaded enemy definitions
+const EDITOR_ENEMY_BRUSH_KEY: KeyCode = KeyCode::Key7;
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EditorRoom>();
+        app.init_resource::<EditorInfo>();
+
+        app.add_systems(
+            OnTransition {
+                from: GlobalState::AssetLoading,
+                to: GlobalState::MainMenu,
+            },
+            init_resources,
+        );
+
+        app.add_systems(OnEnter(GlobalState::Editor), setup_editor);
+        app.add_systems(OnExit(GlobalState::Editor), remove_all_with::<EditorObject>);
+
+        app.add_systems(
+            OnTransition {
+                from: GlobalState::Editor,
+                to: GlobalState::InGame,
+            },
+            spawn_play_test_level,
+        );
+
+        app.add_systems(
+            Update,
+            (
+                editor_camera_move,
+                editor_select_brush,
+                editor_paint,
+                editor_save_load,
+                editor_exit,
+                editor_update_cells,
+                editor_update_text,
+            )
+                .run_if(in_state(GlobalState::Editor)),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Brush {
+    Column,
+    Door,
+    Light,
+    Pistol,
+    Shotgun,
+    Minigun,
+    Enemy(EnemyType),
+    Player,
+}
+
+impl Brush {
+    // Returns cell this brush produces at the given position
+    // or None if it can not be placed there.
+    // Border cells can only be columns or doors.
+    fn cell(self, (x, y): (usize, usize)) -> Option<CellType> {
+        let on_border = x == 0 || x == GRID_SIZE - 1 || y == 0 || y == GRID_SIZE - 1;
+        let cell = match self {
+            Brush::Column => return Some(CellType::Column),
+            Brush::Door => return border_door((x, y)),
+            Brush::Light => CellType::Light,
+            Brush::Pistol => CellType::Weapon(WeaponType::Pistol),
+            Brush::Shotgun => CellType::Weapon(WeaponType::Shotgun),
+            Brush::Minigun => CellType::Weapon(WeaponType::Minigun),
+            Brush::Enemy(enemy_type) => CellType::Enemy(enemy_type),
+            Brush::Player => CellType::Player,
+        };
+        if on_border {
+            None
+        } else {
+            Some(cell)
+        }
+    }
+}
+
+// Door type is picked by the side of the room
+// the door is placed on. Corners can not have doors.
+fn border_door((x, y): (usize, usize)) -> Option<CellType> {
+    let last = GRID_SIZE - 1;
+    if (x == 0 || x == last) && (y == 0 || y == last) {
+        return None;
+    }
+    let (door_type, grid_pos) = if y == 0 {
+        (DoorType::Top, x)
+    } else if y == last {
+        (DoorType::Bottom, x)
+    } else if x == 0 {
+        (DoorType::Left, y)
+    } else if x == last {
+        (DoorType::Right, y)
+    } else {
+        return None;
+    };
+    Some(CellType::Door(Door {
+        door_type,
+        door_state: DoorState::Locked,
+        grid_pos,
+    }))
+}
+
+// Room being edited. It is kept between
+// editor sessions, so after play test
+// the editing can be continued.
+#[derive(Resource)]
+pub struct EditorRoom {
+    pub layout: LevelLayout,
+}
+
+impl Default for EditorRoom {
+    fn default() -> Self {
+        let mut grid = [[CellType::Empty; GRID_SIZE]; GRID_SIZE];
+        grid[0] = [CellType::Column; GRID_SIZE];
+        grid[GRID_SIZE - 1] = [CellType::Column; GRID_SIZE];
+        for row in grid.iter_mut() {
+            row[0] = CellType::Column;
+            row[GRID_SIZE - 1] = CellType::Column;
+        }
+        let middle = GRID_SIZE / 2;
+        for pos in [
+            (middle, 0),
+            (middle, GRID_SIZE - 1),
+            (0, middle),
+            (GRID_SIZE - 1, middle),
+        ] {
+            grid[pos.1][pos.0] = border_door(pos).unwrap();
+        }
+        grid[GRID_SIZE - 2][middle] = CellType::Player;
+        Self {
+            layout: LevelLayout { grid },
+        }
+    }
+}
+
+#[derive(Resource)]
+struct EditorInfo {
+    brush: Brush,
+    message: String,
+}
+
+impl Default for EditorInfo {
+    fn default() -> Self {
+        Self {
+            brush: Brush::Column,
+            message: String::new(),
+        }
+    }
+}
+
+#[derive(Resource)]
+struct EditorResources {
+    cell_mesh: Handle<Mesh>,
+    floor_material: Handle<StandardMaterial>,
+    cursor_material: Handle<StandardMaterial>,
+    column_material: Handle<StandardMaterial>,
+    door_material: Handle<StandardMaterial>,
+    light_material: Handle<StandardMaterial>,
+    weapon_material: Handle<StandardMaterial>,
+    enemy_material: Handle<StandardMaterial>,
+    player_material: Handle<StandardMaterial>,
+}
+
+impl EditorResources {
+    fn cell_material(&self, cell: CellType) -> Option<Handle<StandardMaterial>> {
+        match cell {
+            CellType::Empty => None,
+            CellType::Door(_) => Some(self.door_material.clone()),
+            CellType::Column => Some(self.column_material.clone()),
+            CellType::Light => Some(self.light_material.clone()),
+            CellType::Weapon(_) => Some(self.weapon_material.clone()),
+            CellType::Enemy(_) => Some(self.enemy_material.clone()),
+            CellType::Player => Some(self.player_material.clone()),
+        }
+    }
+}
+
+// All entities spawned by the editor.
+// Removed on editor exit.
+#[derive(Component)]
+struct EditorObject;
+
+#[derive(Component)]
+struct EditorCamera;
+
+#[derive(Component)]
+struct EditorCell;
+
+#[derive(Component)]
+struct EditorCursor;
+
+#[derive(Component)]
+struct EditorText;
+
+fn cell_translation((x, y): (usize, usize)) -> Vec3 {
+    let x_pos = (-LEVEL_SIZE / 2.0) + COLUMN_SIZE * x as f32 + COLUMN_SIZE / 2.0;
+    let y_pos = (LEVEL_SIZE / 2.0) - COLUMN_SIZE * y as f32 - COLUMN_SIZE / 2.0;
+    Vec3::new(x_pos, y_pos, EDITOR_CELL_HIGHT)
+}
+
+fn world_to_cell(position: Vec3) -> Option<(usize, usize)> {
+    let x = ((position.x + LEVEL_SIZE / 2.0) / COLUMN_SIZE).floor();
+    let y = ((LEVEL_SIZE / 2.0 - position.y) / COLUMN_SIZE).floor();
+    if x < 0.0 || GRID_SIZE as f32 <= x || y < 0.0 || GRID_SIZE as f32 <= y {
+        None
+    } else {
+        Some((x as usize, y as usize))
+    }
+}
+
+fn init_resources(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let cell_mesh =
+        meshes.add(shape::Box::new(EDITOR_CELL_SIZE, EDITOR_CELL_SIZE, EDITOR_CELL_HIGHT).into());
+
+    let mut unlit = |color: Color| {
+        materials.add(StandardMaterial {
+            base_color: color,
+            unlit: true,
+            ..default()
+        })
+    };
+
+    let floor_material = unlit(Color::GRAY);
+    let column_material = unlit(Color::DARK_GRAY);
+    let door_material = unlit(Color::ORANGE);
+    let light_material = unlit(Color::YELLOW);
+    let weapon_material = unlit(Color::BLUE);
+    let enemy_material = unlit(Color::RED);
+    let player_material = unlit(Color::GREEN);
+    let cursor_material = materials.add(StandardMaterial {
+        base_color: Color::rgba(1.0, 1.0, 1.0, 0.5),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+
+    commands.insert_resource(EditorResources {
+        cell_mesh,
+        floor_material,
+        cursor_material,
+        column_material,
+        door_material,
+        light_material,
+        weapon_material,
+        enemy_material,
+        player_material,
+    });
+}
+
+fn setup_editor(
+    ui_assets: Res<UiAssets>,
+    level_resources: Res<LevelResources>,
+    editor_resources: Res<EditorResources>,
+    mut editor_room: ResMut<EditorRoom>,
+    mut commands: Commands,
+) {
+    // force cells to be spawned
+    editor_room.set_changed();
+
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 0.0, EDITOR_CAMERA_HEIGHT)
+                .looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        EditorCamera,
+        EditorObject,
+    ));
+
+    commands.spawn((
+        PbrBundle {
+            mesh: level_resources.floor_mesh.clone(),
+            material: editor_resources.floor_material.clone(),
+            ..default()
+        },
+        EditorObject,
+    ));
+
+    commands.spawn((
+        PbrBundle {
+            mesh: editor_resources.cell_mesh.clone(),
+            material: editor_resources.cursor_material.clone(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        EditorCursor,
+        EditorObject,
+    ));
+
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: ui_assets.font.clone(),
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        },
+        EditorText,
+        EditorObject,
+    ));
+}
+
+fn editor_camera_move(
+    keys: Res<Input<KeyCode>>,
+    mut camera: Query<&mut Transform, With<EditorCamera>>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+) {
+    let Ok(mut transform) = camera.get_single_mut() else {
+        return;
+    };
+
+    let mut movement = Vec3::ZERO;
+    if keys.pressed(KeyCode::W) {
+        movement.y += 1.0;
+    }
+    if keys.pressed(KeyCode::S) {
+        movement.y -= 1.0;
+    }
+    if keys.pressed(KeyCode::A) {
+        movement.x -= 1.0;
+    }
+    if keys.pressed(KeyCode::D) {
+        movement.x += 1.0;
+    }
+    // move faster when zoomed out
+    let speed = EDITOR_CAMERA_SPEED * transform.translation.z / 100.0;
+    transform.translation += movement * speed;
+
+    for event in mouse_wheel.read() {
+        transform.translation.z = (transform.translation.z - event.y * EDITOR_CAMERA_ZOOM_SPEED)
+            .clamp(EDITOR_CAMERA_MIN_HEIGHT, EDITOR_CAMERA_MAX_HEIGHT);
+    }
+}
+
+fn editor_select_brush(
+    keys: Res<Input<KeyCode>>,
+    enemy_definitions: Res<EnemyDefinitions>,
+    mut editor_info: ResMut<EditorInfo>,
+) {
+    for (key, brush) in EDITOR_BRUSH_KEYS {
+        if keys.just_pressed(key) {
+            editor_info.brush = brush;
+        }
+    }
+
+    if keys.just_pressed(EDITOR_ENEMY_BRUSH_KEY) {
+        let current = match editor_info.brush {
+            Brush::Enemy(enemy_type) => Some(enemy_type),
+            _ => None,
+        };
+        // wraps around to the first enemy
+        let next = enemy_definitions
+            .types()
+            .find(|enemy_type| current < Some(*enemy_type))
+            .or_else(|| enemy_definitions.types().next());
+        if let Some(next) = next {
+            editor_info.brush = Brush::Enemy(next);
+        }
+    }
+}
+
+fn editor_paint(
+    mouse: Res<Input<MouseButton>>,
+    windows: Query<&Window>,
+    camera: Query<(&Camera, &GlobalTransform), With<EditorCamera>>,
+    mut cursor: Query<(&mut Transform, &mut Visibility), With<EditorCursor>>,
+    mut editor_room: ResMut<EditorRoom>,
+    mut editor_info: ResMut<EditorInfo>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera.get_single() else {
+        return;
+    };
+    let Ok((mut cursor_transform, mut cursor_visibility)) = cursor.get_single_mut() else {
+        return;
+    };
+
+    let cell = window
+        .cursor_position()
+        .and_then(|position| camera.viewport_to_world(camera_transform, position))
+        .and_then(|ray| {
+            ray.intersect_plane(Vec3::ZERO, Vec3::Z)
+                .map(|distance| ray.get_point(distance))
+        })
+        .and_then(world_to_cell);
+
+    let Some((x, y)) = cell else {
+        *cursor_visibility = Visibility::Hidden;
+        return;
+    };
+    *cursor_visibility = Visibility::Visible;
+    cursor_transform.translation = cell_translation((x, y)) + Vec3::Z * EDITOR_CELL_HIGHT;
+
+    let new_cell = if mouse.pressed(MouseButton::Left) {
+        let Some(new_cell) = editor_info.brush.cell((x, y)) else {
+            editor_info.message = format!("Can not place {:?} here", editor_info.brush);
+            return;
+        };
+        new_cell
+    } else if mouse.pressed(MouseButton::Right) {
+        let on_border = x == 0 || x == GRID_SIZE - 1 || y == 0 || y == GRID_SIZE - 1;
+        if on_border {
+            CellType::Column
+        } else {
+            CellType::Empty
+        }
+    } else {
+        return;
+    };
+
+    if editor_room.layout.grid[y][x] == new_cell {
+        return;
+    }
+
+    // there can be only one player
+    if new_cell == CellType::Player {
+        for cell in editor_room.layout.grid.iter_mut().flatten() {
+            if *cell == CellType::Player {
+                *cell = CellType::Empty;
+            }
+        }
+    }
+    editor_room.layout.grid[y][x] = new_cell;
+}
+
+fn editor_save_load(
+    keys: Res<Input<KeyCode>>,
+    mut editor_room: ResMut<EditorRoom>,
+    mut editor_info: ResMut<EditorInfo>,
+) {
+    if keys.just_pressed(KeyCode::F5) {
+        // only save rooms that can be loaded back
+        let text = editor_room.layout.to_string();
+        editor_info.message = match parse_room(&text) {
+            Ok(_) => match std::fs::write(EDITOR_ROOM_PATH, text) {
+                Ok(_) => format!("Saved to {EDITOR_ROOM_PATH}"),
+                Err(e) => format!("Could not save: {e}"),
+            },
+            Err(e) => format!("Could not save: {e}"),
+        };
+    }
+
+    if keys.just_pressed(KeyCode::F9) {
+        editor_info.message = match std::fs::read_to_string(EDITOR_ROOM_PATH) {
+            Ok(text) => match parse_room(&text) {
+                Ok(layout) => {
+                    editor_room.layout = layout;
+                    format!("Loaded {EDITOR_ROOM_PATH}")
+                }
+                Err(e) => format!("Could not load: {e}"),
+            },
+            Err(e) => format!("Could not load: {e}"),
+        };
+    }
+}
+
+fn editor_exit(
+    keys: Res<Input<KeyCode>>,
+    editor_room: Res<EditorRoom>,
+    mut editor_info: ResMut<EditorInfo>,
+    mut global_state: ResMut<NextState<GlobalState>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        global_state.set(GlobalState::MainMenu);
+    }
+
+    if keys.just_pressed(KeyCode::Return) {
+        let has_player = editor_room
+            .layout
+            .grid
+            .iter()
+            .flatten()
+            .any(|cell| *cell == CellType::Player);
+        if has_player {
+            global_state.set(GlobalState::InGame);
+        } else {
+            editor_info.message = "Place the player to play test".to_string();
+        }
+    }
+}
+
+fn editor_update_cells(
+    editor_resources: Res<EditorResources>,
+    editor_room: Res<EditorRoom>,
+    cells: Query<Entity, With<EditorCell>>,
+    mut commands: Commands,
+) {
+    if !editor_room.is_changed() {
+        return;
+    }
+
+    for cell in cells.iter() {
+        commands.entity(cell).despawn_recursive();
+    }
+
+    for (y, row) in editor_room.layout.grid.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            let Some(material) = editor_resources.cell_material(*cell) else {
+                continue;
+            };
+            commands.spawn((
+                PbrBundle {
+                    mesh: editor_resources.cell_mesh.clone(),
+                    material,
+                    transform: Transform::from_translation(cell_translation((x, y))),
+                    ..default()
+                },
+                EditorCell,
+                EditorObject,
+            ));
+        }
+    }
+}
+
+fn editor_update_text(
+    enemy_definitions: Res<EnemyDefinitions>,
+    editor_info: Res<EditorInfo>,
+    mut text: Query<&mut Text, With<EditorText>>,
+) {
+    let Ok(mut text) = text.get_single_mut() else {
+        return;
+    };
+    if !editor_info.is_changed() && !text.sections[0].value.is_empty() {
+        return;
+    }
+    let brush = match editor_info.brush {
+        Brush::Enemy(enemy_type) => enemy_definitions.get(enemy_type).map_or_else(
+            || format!("{enemy_type:?}"),
+            |definition| definition.name.clone(),
+        ),
+        brush => format!("{brush:?}"),
+    };
+    text.sections[0].value = format!(
+        "Brush: {brush}\n{}\n\n{EDITOR_HELP_TEXT}",
+        editor_info.message
+    );
+}
+
+fn spawn_play_test_level(
+    spawn_resources: LevelSpawnResources,
+    run_settings: RunSettings,
+    editor_room: Res<EditorRoom>,
+    difficulty: Res<Difficulty>,
+    mut run_seed: ResMut<RunSeed>,
+    mut commands: Commands,
+    mut level_started_events: EventWriter<LevelStarted>,
+) {
+    spawn_level(
+        &spawn_resources,
+        &mut commands,
+        &editor_room.layout,
+        &difficulty.step(0.0),
+        Vec3::ZERO,
+        None,
+        LevelType::Covered,
+    );
+
+    commands.insert_resource(run_settings.level_info(0));
+    commands.insert_resource(run_seed.new_run());
+    commands.insert_resource(run_seed.combat_rng());
+
+    // player is already inside the room, so
+    // enemies are enabled right away
+    level_started_events.send(LevelStarted);
+}
@@ -1,25 +1,23 @@
-use bevy::prelude::*;
-use bevy_rapier3d::prelude::*;
-use rand::Rng;
-
-use crate::{
-    enemies::{spawn_enemy, EnemyAssets, EnemyType},
-    player::{spawn_player, PlayerResources},
-    ui::UiResources,
-    weapons::{spawn_weapon, WeaponAssets, WeaponType},
-};
+use std::{collections::VecDeque, fmt::Display};
+
+use rand::{Rng, RngCore};
+
+use crate::{difficulty::DifficultyStep, enemies::EnemyType, weapons::WeaponType};
 
 use super::{
-    door::{spawn_door, Door, DoorState, DoorType},
-    spawn_light, LevelAssets, LevelColliderBundle, LevelObject, LevelResources, LevelType,
-    COLUMN_HIGHT, COLUMN_SIZE, FILL_AMOUNT, FLOOR_THICKNESS, GRID_SIZE, LEVEL_ENEMIES,
-    LEVEL_LIGHTS_COVERAGE, LEVEL_SIZE, LEVEL_SMALL_ENEMIES_PERCENT,
-    LEVEL_WEAPON_PISTOL_SPAWN_THRESHOLD, LEVEL_WEAPON_SHOTGUN_SPAWN_THRESHOLD, LEVEL_WEAPON_SPAWNS,
-    STRIP_LENGTH,
+    door::{Door, DoorState, DoorType},
+    wfc::WfcGenerator,
+    FILL_AMOUNT, GRID_SIZE, LEVEL_LIGHTS_COVERAGE, LEVEL_WEAPON_SPAWNS, STRIP_LENGTH,
 };
 
+// Enemies of the boss level. Boss is surrounded
+// by a ring of guards and a ring of minions.
+const BOSS_ROOM_BOSS: EnemyType = EnemyType('6');
+const BOSS_ROOM_GUARD: EnemyType = EnemyType('2');
+const BOSS_ROOM_MINION: EnemyType = EnemyType('1');
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum CellType {
+pub enum CellType {
     Empty,
     Door(Door),
     Column,
@@ -29,20 +27,91 @@ enum CellType {
     Player,
 }
 
+impl CellType {
+    pub fn to_char(self) -> char {
+        match self {
+            CellType::Empty => '.',
+            CellType::Door(door) => match door.door_type {
+                DoorType::Top => 'T',
+                DoorType::Bottom => 'B',
+                DoorType::Left => 'L',
+                DoorType::Right => 'R',
+            },
+            CellType::Column => '#',
+            CellType::Light => '*',
+            CellType::Weapon(WeaponType::Pistol) => 'p',
+            CellType::Weapon(WeaponType::Shotgun) => 's',
+            CellType::Weapon(WeaponType::Minigun) => 'm',
+            CellType::Enemy(enemy_type) => enemy_type.0,
+            CellType::Player => '@',
+        }
+    }
+}
+
+// Layout of a single level. Does not depend on
+// ECS or assets, so it can be generated and
+// inspected without running the game.
 // ^ y
 // |
 // -->x
-fn generate_boss_level(previus_door: Option<Door>) -> [[CellType; GRID_SIZE]; GRID_SIZE] {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LevelLayout {
     // row order
-    let mut grid = [[CellType::Empty; GRID_SIZE]; GRID_SIZE];
+    pub grid: [[CellType; GRID_SIZE]; GRID_SIZE],
+}
 
-    // generate border
-    for x in 0..GRID_SIZE {
-        grid[0][x] = CellType::Column;
+impl Display for LevelLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.grid.iter() {
+            let line = row.iter().map(|cell| cell.to_char()).collect::<String>();
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
     }
-    for x in 0..GRID_SIZE {
-        grid[GRID_SIZE - 1][x] = CellType::Column;
+}
+
+impl LevelLayout {
+    // Leaves only a small corridor with the player,
+    // a pistol and the top door.
+    pub fn carve_tutorial(&mut self) {
+        let grid = &mut self.grid;
+        let mut player_pos = (0, 0);
+
+        // remove all content from the level
+        for (y, row) in grid.iter_mut().enumerate().take(GRID_SIZE - 1).skip(1) {
+            for (x, cell) in row.iter_mut().enumerate().take(GRID_SIZE - 1).skip(1) {
+                if *cell != CellType::Player {
+                    *cell = CellType::Empty;
+                } else {
+                    player_pos = (y, x);
+                }
+            }
+        }
+
+        // move player back
+        let new_player_pos = (player_pos.0 + 3, player_pos.1);
+        grid[player_pos.0][player_pos.1] = CellType::Light;
+        grid[player_pos.0 + 1][player_pos.1] = CellType::Weapon(WeaponType::Pistol);
+        grid[new_player_pos.0][new_player_pos.1] = CellType::Player;
+
+        // place walls around player
+        for row in grid.iter_mut() {
+            row[new_player_pos.1 - 2] = CellType::Column;
+        }
+        for row in grid.iter_mut() {
+            row[new_player_pos.1 + 2] = CellType::Column;
+        }
+        grid[new_player_pos.0 + 2].fill(CellType::Column);
     }
+}
+
+pub fn generate_boss_level(previus_door: Option<Door>) -> LevelLayout {
+    // row order
+    let mut grid = [[CellType::Empty; GRID_SIZE]; GRID_SIZE];
+
+    // generate border
+    grid[0].fill(CellType::Column);
+    grid[GRID_SIZE - 1].fill(CellType::Column);
     (0..GRID_SIZE).for_each(|y| {
         grid[y][0] = CellType::Column;
     });
@@ -83,35 +152,27 @@ fn generate_boss_level(previus_door: Option<Door>) -> [[CellType; GRID_SIZE]; GR
     }
 
     let middle = GRID_SIZE / 2;
-    // Big
-    grid[middle][middle] = CellType::Enemy(EnemyType::Big);
+    // Boss
+    grid[middle][middle] = CellType::Enemy(BOSS_ROOM_BOSS);
 
     // Mid
-    for x in middle - 1..=middle + 1 {
-        grid[middle + 1][x] = CellType::Enemy(EnemyType::Mid);
-    }
-    for x in middle - 1..=middle + 1 {
-        grid[middle - 1][x] = CellType::Enemy(EnemyType::Mid);
+    grid[middle + 1][middle - 1..=middle + 1].fill(CellType::Enemy(BOSS_ROOM_GUARD));
+    grid[middle - 1][middle - 1..=middle + 1].fill(CellType::Enemy(BOSS_ROOM_GUARD));
+    for row in &mut grid[middle - 1..=middle + 1] {
+        row[middle + 1] = CellType::Enemy(BOSS_ROOM_GUARD);
     }
-    for y in middle - 1..=middle + 1 {
-        grid[y][middle + 1] = CellType::Enemy(EnemyType::Mid);
-    }
-    for y in middle - 1..=middle + 1 {
-        grid[y][middle - 1] = CellType::Enemy(EnemyType::Mid);
+    for row in &mut grid[middle - 1..=middle + 1] {
+        row[middle - 1] = CellType::Enemy(BOSS_ROOM_GUARD);
     }
 
     // Small
-    for x in middle - 2..=middle + 2 {
-        grid[middle + 2][x] = CellType::Enemy(EnemyType::Small);
-    }
-    for x in middle - 2..=middle + 2 {
-        grid[middle - 2][x] = CellType::Enemy(EnemyType::Small);
+    grid[middle + 2][middle - 2..=middle + 2].fill(CellType::Enemy(BOSS_ROOM_MINION));
+    grid[middle - 2][middle - 2..=middle + 2].fill(CellType::Enemy(BOSS_ROOM_MINION));
+    for row in &mut grid[middle - 2..=middle + 2] {
+        row[middle + 2] = CellType::Enemy(BOSS_ROOM_MINION);
     }
-    for y in middle - 2..=middle + 2 {
-        grid[y][middle + 2] = CellType::Enemy(EnemyType::Small);
-    }
-    for y in middle - 2..=middle + 2 {
-        grid[y][middle - 2] = CellType::Enemy(EnemyType::Small);
+    for row in &mut grid[middle - 2..=middle + 2] {
+        row[middle - 2] = CellType::Enemy(BOSS_ROOM_MINION);
     }
 
     // Top right corner
@@ -158,25 +219,130 @@ fn generate_boss_level(previus_door: Option<Door>) -> [[CellType; GRID_SIZE]; GR
     grid[GRID_SIZE - 3][GRID_SIZE - 5] = CellType::Weapon(WeaponType::Minigun);
     grid[GRID_SIZE - 5][GRID_SIZE - 3] = CellType::Weapon(WeaponType::Minigun);
 
-    grid
+    connect_level(&mut grid);
+
+    LevelLayout { grid }
 }
 
-// ^ y
-// |
-// -->x
-fn generate_normal_level(previus_door: Option<Door>) -> [[CellType; GRID_SIZE]; GRID_SIZE] {
-    let mut rng = rand::thread_rng();
+// Fills the inside of a normal level with columns.
+// Border, doors and the player are already placed
+// and must not be touched. Everything else is placed
+// after the walls.
+pub trait RoomGenerator {
+    fn generate_walls(&self, rng: &mut dyn RngCore, grid: &mut [[CellType; GRID_SIZE]; GRID_SIZE]);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoomGeneratorType {
+    Strips,
+    Wfc,
+}
+
+impl RoomGeneratorType {
+    pub fn generator(self) -> &'static dyn RoomGenerator {
+        match self {
+            RoomGeneratorType::Strips => &StripGenerator,
+            RoomGeneratorType::Wfc => &WfcGenerator,
+        }
+    }
+}
+
+// Places random short strips of columns
+pub struct StripGenerator;
+
+impl RoomGenerator for StripGenerator {
+    fn generate_walls(&self, rng: &mut dyn RngCore, grid: &mut [[CellType; GRID_SIZE]; GRID_SIZE]) {
+        let fill_cells = (GRID_SIZE as f32 * GRID_SIZE as f32 * FILL_AMOUNT) as u32;
+        let num_strips = fill_cells / STRIP_LENGTH;
+        for _ in 0..num_strips {
+            let random_cell_x = rng.gen_range(2..GRID_SIZE - 2);
+            let random_cell_y = rng.gen_range(2..GRID_SIZE - 2);
+            grid[random_cell_y][random_cell_x] = CellType::Column;
+
+            let mut current_x: i32 = random_cell_x as i32;
+            let mut current_y: i32 = random_cell_y as i32;
+
+            for _ in 0..STRIP_LENGTH {
+                let mods = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+                let valid_pos = mods
+                    .iter()
+                    .filter_map(|(x_mod, y_mod)| {
+                        let (next_x, next_y) = (current_x + x_mod, current_y + y_mod);
+                        if next_x < 2
+                            || GRID_SIZE as i32 - 2 <= next_x
+                            || next_y < 2
+                            || GRID_SIZE as i32 - 2 <= next_y
+                        {
+                            None
+                        } else {
+                            Some((next_x, next_y))
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                if valid_pos.is_empty() {
+                    break;
+                }
+
+                let random_cell = rng.gen_range(0..valid_pos.len());
+                (current_x, current_y) = valid_pos[random_cell];
+                grid[current_y as usize][current_x as usize] = CellType::Column;
+            }
+        }
+    }
+}
 
+// Adapts hand authored room to the level sequence. Authored doors
+// on the entrance side are walled up and replaced with a single
+// door mirrored to the previous exit, so the player can walk in.
+pub fn generate_authored_level(room: &LevelLayout, previus_door: Door) -> LevelLayout {
+    let mut grid = room.grid;
+
+    let entrance_type = match previus_door.door_type {
+        DoorType::Top => DoorType::Bottom,
+        DoorType::Bottom => DoorType::Top,
+        DoorType::Left => DoorType::Right,
+        DoorType::Right => DoorType::Left,
+    };
+
+    for cell in grid.iter_mut().flatten() {
+        match cell {
+            CellType::Player => *cell = CellType::Empty,
+            CellType::Door(door) if door.door_type == entrance_type => *cell = CellType::Column,
+            _ => {}
+        }
+    }
+
+    let pos = previus_door.grid_pos;
+    let (y, x) = match entrance_type {
+        DoorType::Top => (0, pos),
+        DoorType::Bottom => (GRID_SIZE - 1, pos),
+        DoorType::Left => (pos, 0),
+        DoorType::Right => (pos, GRID_SIZE - 1),
+    };
+    grid[y][x] = CellType::Door(Door {
+        door_type: entrance_type,
+        door_state: DoorState::TemporaryOpen,
+        grid_pos: pos,
+    });
+
+    connect_level(&mut grid);
+
+    LevelLayout { grid }
+}
+
+pub fn generate_normal_level(
+    generator: &dyn RoomGenerator,
+    difficulty: &DifficultyStep,
+    rng: &mut impl Rng,
+    previus_door: Option<Door>,
+) -> LevelLayout {
     // row order
     let mut grid = [[CellType::Empty; GRID_SIZE]; GRID_SIZE];
 
     // generate border
-    for x in 0..GRID_SIZE {
-        grid[0][x] = CellType::Column;
-    }
-    for x in 0..GRID_SIZE {
-        grid[GRID_SIZE - 1][x] = CellType::Column;
-    }
+    grid[0].fill(CellType::Column);
+    grid[GRID_SIZE - 1].fill(CellType::Column);
     (0..GRID_SIZE).for_each(|y| {
         grid[y][0] = CellType::Column;
     });
@@ -246,44 +412,7 @@ fn generate_normal_level(previus_door: Option<Door>) -> [[CellType; GRID_SIZE];
         grid_pos: door_right_pos,
     });
 
-    // generate walls
-    let fill_cells = (GRID_SIZE as f32 * GRID_SIZE as f32 * FILL_AMOUNT) as u32;
-    let num_strips = fill_cells / STRIP_LENGTH;
-    for _ in 0..num_strips {
-        let random_cell_x = rng.gen_range(2..GRID_SIZE - 2);
-        let random_cell_y = rng.gen_range(2..GRID_SIZE - 2);
-        grid[random_cell_y][random_cell_x] = CellType::Column;
-
-        let mut current_x: i32 = random_cell_x as i32;
-        let mut current_y: i32 = random_cell_y as i32;
-
-        for _ in 0..STRIP_LENGTH {
-            let mods = [(-1, 0), (1, 0), (0, -1), (0, 1)];
-            let valid_pos = mods
-                .iter()
-                .filter_map(|(x_mod, y_mod)| {
-                    let (next_x, next_y) = (current_x + x_mod, current_y + y_mod);
-                    if next_x < 2
-                        || GRID_SIZE as i32 - 2 <= next_x
-                        || next_y < 2
-                        || GRID_SIZE as i32 - 2 <= next_y
-                    {
-                        None
-                    } else {
-                        Some((next_x, next_y))
-                    }
-                })
-                .collect::<Vec<_>>();
-
-            if valid_pos.is_empty() {
-                break;
-            }
-
-            let random_cell = rng.gen_range(0..valid_pos.len());
-            (current_x, current_y) = valid_pos[random_cell];
-            grid[current_y as usize][current_x as usize] = CellType::Column;
-        }
-    }
+    generator.generate_walls(rng, &mut grid);
 
     // check if there are some "trapped" places and remove them
     for y in 2..GRID_SIZE - 2 {
@@ -309,19 +438,26 @@ fn generate_normal_level(previus_door: Option<Door>) -> [[CellType; GRID_SIZE];
             random_cell_y = rng.gen_range(2..GRID_SIZE - 2);
         }
 
-        let random = rng.gen_range(0.0..1.0);
-        if random < LEVEL_WEAPON_PISTOL_SPAWN_THRESHOLD {
-            grid[random_cell_y][random_cell_x] = CellType::Weapon(WeaponType::Pistol);
-        }
-        if random < LEVEL_WEAPON_SHOTGUN_SPAWN_THRESHOLD {
-            grid[random_cell_y][random_cell_x] = CellType::Weapon(WeaponType::Shotgun);
-        } else {
-            grid[random_cell_y][random_cell_x] = CellType::Weapon(WeaponType::Minigun);
-        }
+        let weapon_type = match weighted_index(rng, &difficulty.weapon_mix) {
+            0 => WeaponType::Pistol,
+            1 => WeaponType::Shotgun,
+            _ => WeaponType::Minigun,
+        };
+        grid[random_cell_y][random_cell_x] = CellType::Weapon(weapon_type);
     }
 
     // generate enemies
-    for _ in 0..LEVEL_ENEMIES {
+    let enemy_weights = difficulty
+        .enemy_mix
+        .iter()
+        .map(|(_, weight)| *weight)
+        .collect::<Vec<_>>();
+    let enemies = if difficulty.enemy_mix.is_empty() {
+        0
+    } else {
+        difficulty.enemies
+    };
+    for _ in 0..enemies {
         let mut random_cell_x = rng.gen_range(2..GRID_SIZE - 2);
         let mut random_cell_y = rng.gen_range(2..GRID_SIZE - 2);
 
@@ -330,11 +466,8 @@ fn generate_normal_level(previus_door: Option<Door>) -> [[CellType; GRID_SIZE];
             random_cell_y = rng.gen_range(2..GRID_SIZE - 2);
         }
 
-        if rng.gen_bool(LEVEL_SMALL_ENEMIES_PERCENT) {
-            grid[random_cell_y][random_cell_x] = CellType::Enemy(EnemyType::Small);
-        } else {
-            grid[random_cell_y][random_cell_x] = CellType::Enemy(EnemyType::Mid);
-        }
+        let (enemy_type, _) = difficulty.enemy_mix[weighted_index(rng, &enemy_weights)];
+        grid[random_cell_y][random_cell_x] = CellType::Enemy(enemy_type);
     }
 
     // generate lights
@@ -346,191 +479,336 @@ fn generate_normal_level(previus_door: Option<Door>) -> [[CellType; GRID_SIZE];
         }
     }
 
-    // for row in grid.iter() {
-    //     for cell in row.iter() {
-    //         match cell {
-    //             CellType::Player => print!("p"),
-    //             CellType::Enemy => print!("e"),
-    //             CellType::Empty => print!(" "),
-    //             CellType::Column => print!("#"),
-    //             CellType::Weapon => print!("w"),
-    //             CellType::Door(d) => match d.door_type {
-    //                 DoorType::Bottom => print!("B"),
-    //                 DoorType::Top => print!("T"),
-    //                 DoorType::Left => print!("L"),
-    //                 DoorType::Right => print!("R"),
-    //             },
-    //         }
-    //     }
-    //     println!();
-    // }
-
-    grid
+    connect_level(&mut grid);
+
+    LevelLayout { grid }
 }
 
-#[allow(clippy::too_many_arguments)]
-pub fn spawn_level(
-    ui_resources: &UiResources,
-    level_assets: &LevelAssets,
-    enemy_assets: &EnemyAssets,
-    weapon_assets: &WeaponAssets,
-    level_resources: &LevelResources,
-    player_resources: &PlayerResources,
-    commands: &mut Commands,
-    level_translation: Vec3,
-    previus_door: Option<Door>,
-    level_type: LevelType,
-    tutorial_level: bool,
-    boss_level: bool,
-) -> Vec3 {
-    let mut grid = if boss_level {
-        generate_boss_level(previus_door)
-    } else {
-        generate_normal_level(previus_door)
-    };
+// Picks random index with probability
+// proportional to its weight
+fn weighted_index(rng: &mut impl Rng, weights: &[u32]) -> usize {
+    let total = weights.iter().sum::<u32>();
+    let mut random = rng.gen_range(0..total.max(1));
+    for (i, weight) in weights.iter().enumerate() {
+        if random < *weight {
+            return i;
+        }
+        random -= weight;
+    }
+    0
+}
 
-    if tutorial_level {
-        let mut player_pos = (0, 0);
+fn is_walkable(cell: CellType) -> bool {
+    !matches!(cell, CellType::Column | CellType::Door(_))
+}
 
-        // remove all content from the level
-        for y in 1..GRID_SIZE - 1 {
-            for x in 1..GRID_SIZE - 1 {
-                if grid[y][x] != CellType::Player {
-                    grid[y][x] = CellType::Empty;
-                } else {
-                    player_pos = (y, x);
-                }
+// Neighbours of the inner cell which are not on the level border
+fn inner_neighbours((x, y): (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+    [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+        .into_iter()
+        .filter(|(x, y)| 0 < *x && *x < GRID_SIZE - 1 && 0 < *y && *y < GRID_SIZE - 1)
+}
+
+// Returns all cells that must be reachable: cells in front
+// of doors, weapons, enemies and the player.
+fn level_targets(grid: &[[CellType; GRID_SIZE]; GRID_SIZE]) -> Vec<(usize, usize)> {
+    let mut targets = vec![];
+    for (y, row) in grid.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            match cell {
+                CellType::Door(door) => targets.push(match door.door_type {
+                    DoorType::Top => (x, y + 1),
+                    DoorType::Bottom => (x, y - 1),
+                    DoorType::Left => (x + 1, y),
+                    DoorType::Right => (x - 1, y),
+                }),
+                CellType::Weapon(_) | CellType::Enemy(_) | CellType::Player => targets.push((x, y)),
+                _ => {}
             }
         }
+    }
+    targets
+}
 
-        // move player back
-        let new_player_pos = (player_pos.0 + 3, player_pos.1);
-        grid[player_pos.0][player_pos.1] = CellType::Light;
-        grid[player_pos.0 + 1][player_pos.1] = CellType::Weapon(WeaponType::Pistol);
-        grid[new_player_pos.0][new_player_pos.1] = CellType::Player;
+fn flood_fill(
+    grid: &[[CellType; GRID_SIZE]; GRID_SIZE],
+    start: (usize, usize),
+) -> [[bool; GRID_SIZE]; GRID_SIZE] {
+    let mut reachable = [[false; GRID_SIZE]; GRID_SIZE];
+    let mut queue = VecDeque::from([start]);
+    reachable[start.1][start.0] = true;
+
+    while let Some((x, y)) = queue.pop_front() {
+        for (next_x, next_y) in inner_neighbours((x, y)) {
+            if !reachable[next_y][next_x] && is_walkable(grid[next_y][next_x]) {
+                reachable[next_y][next_x] = true;
+                queue.push_back((next_x, next_y));
+            }
+        }
+    }
+    reachable
+}
 
-        // place walls around player
-        for y in 0..GRID_SIZE {
-            grid[y][new_player_pos.1 - 2] = CellType::Column;
+// Removes the smallest number of columns needed to connect
+// `from` cell with any of the `reachable` cells.
+fn carve_path(
+    grid: &mut [[CellType; GRID_SIZE]; GRID_SIZE],
+    from: (usize, usize),
+    reachable: &[[bool; GRID_SIZE]; GRID_SIZE],
+) {
+    let mut cost = [[u32::MAX; GRID_SIZE]; GRID_SIZE];
+    let mut parent = [[None; GRID_SIZE]; GRID_SIZE];
+    let mut queue = VecDeque::from([from]);
+    cost[from.1][from.0] = 0;
+
+    // 0-1 BFS where walking through a column costs 1
+    let mut end = None;
+    while let Some((x, y)) = queue.pop_front() {
+        if reachable[y][x] {
+            end = Some((x, y));
+            break;
         }
-        for y in 0..GRID_SIZE {
-            grid[y][new_player_pos.1 + 2] = CellType::Column;
+        for (next_x, next_y) in inner_neighbours((x, y)) {
+            let step = u32::from(grid[next_y][next_x] == CellType::Column);
+            let next_cost = cost[y][x] + step;
+            if next_cost < cost[next_y][next_x] {
+                cost[next_y][next_x] = next_cost;
+                parent[next_y][next_x] = Some((x, y));
+                if step == 0 {
+                    queue.push_front((next_x, next_y));
+                } else {
+                    queue.push_back((next_x, next_y));
+                }
+            }
         }
-        for x in 0..GRID_SIZE {
-            grid[new_player_pos.0 + 2][x] = CellType::Column;
+    }
+
+    let mut current = end;
+    while let Some((x, y)) = current {
+        if grid[y][x] == CellType::Column {
+            grid[y][x] = CellType::Empty;
         }
+        current = parent[y][x];
     }
+}
 
-    let level_translation = match previus_door {
-        Some(door) => match door.door_type {
-            DoorType::Top => level_translation + Vec3::new(0.0, LEVEL_SIZE, 0.0),
-            DoorType::Bottom => level_translation + Vec3::new(0.0, -LEVEL_SIZE, 0.0),
-            DoorType::Left => level_translation + Vec3::new(-LEVEL_SIZE, 0.0, 0.0),
-            DoorType::Right => level_translation + Vec3::new(LEVEL_SIZE, 0.0, 0.0),
-        },
-        None => level_translation,
+// Makes sure doors, weapons, enemies and the player
+// are all reachable from each other. Places that are
+// walled off are connected to the rest of the level
+// by removing columns.
+fn connect_level(grid: &mut [[CellType; GRID_SIZE]; GRID_SIZE]) {
+    let targets = level_targets(grid);
+    let Some(&start) = targets.first() else {
+        return;
     };
 
-    for (y, row) in grid.iter().enumerate() {
-        for (x, cell) in row.iter().enumerate() {
-            let x_pos = (-LEVEL_SIZE / 2.0) + COLUMN_SIZE * x as f32 + COLUMN_SIZE / 2.0;
-            let y_pos = (LEVEL_SIZE / 2.0) - COLUMN_SIZE * y as f32 - COLUMN_SIZE / 2.0;
-            let z_pos = COLUMN_HIGHT / 2.0;
-            let translation = Vec3::new(x_pos, y_pos, z_pos);
-            let transform = Transform::from_translation(translation + level_translation);
+    // cells in front of doors should never be blocked
+    for &(x, y) in targets.iter() {
+        if grid[y][x] == CellType::Column {
+            grid[y][x] = CellType::Empty;
+        }
+    }
 
-            match cell {
-                CellType::Door(door) => {
-                    spawn_door(level_resources, commands, transform, *door);
-                }
-                CellType::Column => {
-                    commands.spawn(LevelColliderBundle::new(
-                        level_resources.column_mesh.clone(),
-                        level_resources.column_material.clone(),
-                        transform,
-                        Collider::cuboid(COLUMN_SIZE / 2.0, COLUMN_SIZE / 2.0, COLUMN_HIGHT / 2.0),
-                    ));
-                }
-                CellType::Light => {
-                    if level_type == LevelType::Covered {
-                        let mut light_transform = transform;
-                        light_transform.translation.z = COLUMN_HIGHT;
-                        spawn_light(level_resources, commands, light_transform);
-                    }
-                }
-                CellType::Weapon(weapon_type) => {
-                    spawn_weapon(weapon_assets, *weapon_type, commands, transform);
-                }
-                CellType::Enemy(enemy_type) => {
-                    spawn_enemy(
-                        enemy_assets,
-                        weapon_assets,
-                        *enemy_type,
-                        commands,
-                        transform,
-                    );
-                }
-                CellType::Player => {
-                    // we spanw player only once, so we can give him
-                    // some default skybox
-                    spawn_player(
-                        ui_resources,
-                        player_resources,
-                        level_assets.normal_skybox.clone(),
-                        commands,
-                        transform,
+    loop {
+        let reachable = flood_fill(grid, start);
+        let Some(&unreachable) = targets.iter().find(|(x, y)| !reachable[*y][*x]) else {
+            return;
+        };
+        carve_path(grid, unreachable, &reachable);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::difficulty::Difficulty;
+
+    use super::*;
+
+    const TEST_SEEDS: u64 = 2000;
+
+    // Every room entrance and the first room without one
+    fn previus_door(seed: u64) -> Option<Door> {
+        let door_type = match seed % 5 {
+            0 => return None,
+            1 => DoorType::Top,
+            2 => DoorType::Bottom,
+            3 => DoorType::Left,
+            _ => DoorType::Right,
+        };
+        Some(Door {
+            door_type,
+            door_state: DoorState::Unlocked,
+            grid_pos: 2 + (seed as usize / 5) % (GRID_SIZE - 4),
+        })
+    }
+
+    fn assert_connected(layout: &LevelLayout, seed: u64) {
+        let targets = level_targets(&layout.grid);
+        let reachable = flood_fill(&layout.grid, targets[0]);
+        for (x, y) in targets {
+            assert!(
+                reachable[y][x],
+                "seed {seed}: ({x}, {y}) is not reachable\n{layout}"
+            );
+        }
+    }
+
+    fn normal_level(seed: u64, previus_door: Option<Door>) -> LevelLayout {
+        let mut rng = StdRng::seed_from_u64(seed);
+        generate_normal_level(
+            &StripGenerator,
+            &Difficulty::Normal.step(0.5),
+            &mut rng,
+            previus_door,
+        )
+    }
+
+    fn count(layout: &LevelLayout, filter: impl Fn(CellType) -> bool) -> usize {
+        layout
+            .grid
+            .iter()
+            .flatten()
+            .filter(|cell| filter(**cell))
+            .count()
+    }
+
+    fn find(layout: &LevelLayout, cell: CellType) -> Option<(usize, usize)> {
+        layout
+            .grid
+            .iter()
+            .enumerate()
+            .find_map(|(y, row)| row.iter().position(|other| *other == cell).map(|x| (x, y)))
+    }
+
+    #[test]
+    fn border_is_walls_and_doors() {
+        for seed in 0..100 {
+            let layout = normal_level(seed, previus_door(seed));
+            for i in 0..GRID_SIZE {
+                for (y, x) in [(0, i), (GRID_SIZE - 1, i), (i, 0), (i, GRID_SIZE - 1)] {
+                    assert!(
+                        matches!(layout.grid[y][x], CellType::Column | CellType::Door(_)),
+                        "seed {seed}: ({x}, {y}) is not a wall\n{layout}"
                     );
                 }
-                CellType::Empty => {}
             }
+            for corner in [0, GRID_SIZE - 1] {
+                assert_eq!(layout.grid[corner][0], CellType::Column);
+                assert_eq!(layout.grid[corner][GRID_SIZE - 1], CellType::Column);
+            }
+            // one door on each side
+            assert_eq!(count(&layout, |cell| matches!(cell, CellType::Door(_))), 4);
+        }
+    }
+
+    #[test]
+    fn previus_door_is_mirrored() {
+        let grid_pos = 5;
+        for (door_type, entrance_type, (y, x)) in [
+            (DoorType::Top, DoorType::Bottom, (GRID_SIZE - 1, grid_pos)),
+            (DoorType::Bottom, DoorType::Top, (0, grid_pos)),
+            (DoorType::Left, DoorType::Right, (grid_pos, GRID_SIZE - 1)),
+            (DoorType::Right, DoorType::Left, (grid_pos, 0)),
+        ] {
+            let door = Door {
+                door_type,
+                door_state: DoorState::Unlocked,
+                grid_pos,
+            };
+            let entrance = CellType::Door(Door {
+                door_type: entrance_type,
+                door_state: DoorState::TemporaryOpen,
+                grid_pos,
+            });
+            assert_eq!(normal_level(0, Some(door)).grid[y][x], entrance);
+            assert_eq!(generate_boss_level(Some(door)).grid[y][x], entrance);
+            // player walks in through the door, so it is not placed
+            assert_eq!(find(&normal_level(0, Some(door)), CellType::Player), None);
         }
     }
 
-    // floor
-    commands.spawn(LevelColliderBundle::new(
-        level_resources.floor_mesh.clone(),
-        level_resources.floor_material.clone(),
-        Transform::from_translation(level_translation),
-        Collider::cuboid(LEVEL_SIZE / 2.0, LEVEL_SIZE / 2.0, 0.5),
-    ));
+    #[test]
+    fn first_level_starts_at_top_door() {
+        let layout = normal_level(0, None);
+        let (x, y) = find(&layout, CellType::Player).unwrap();
+        assert_eq!(y, 1);
+        assert_eq!(
+            layout.grid[0][x],
+            CellType::Door(Door {
+                door_type: DoorType::Top,
+                door_state: DoorState::Unlocked,
+                grid_pos: x,
+            })
+        );
+    }
 
-    // roof
-    if level_type == LevelType::Covered {
-        let mut roof_translation = level_translation;
-        roof_translation.z += COLUMN_HIGHT + FLOOR_THICKNESS / 2.0;
-        commands.spawn(LevelColliderBundle::new(
-            level_resources.floor_mesh.clone(),
-            level_resources.floor_material.clone(),
-            Transform::from_translation(roof_translation),
-            Collider::cuboid(LEVEL_SIZE / 2.0, LEVEL_SIZE / 2.0, 0.5),
-        ));
+    #[test]
+    fn tutorial_is_carved() {
+        let mut layout = normal_level(0, None);
+        let (x, y) = find(&layout, CellType::Player).unwrap();
+        layout.carve_tutorial();
+
+        assert_eq!(find(&layout, CellType::Player), Some((x, y + 3)));
+        assert_eq!(layout.grid[y][x], CellType::Light);
+        assert_eq!(layout.grid[y + 1][x], CellType::Weapon(WeaponType::Pistol));
+        assert!(matches!(layout.grid[0][x], CellType::Door(_)));
+        for i in 0..GRID_SIZE {
+            assert_eq!(layout.grid[i][x - 2], CellType::Column);
+            assert_eq!(layout.grid[i][x + 2], CellType::Column);
+            assert_eq!(layout.grid[y + 5][i], CellType::Column);
+        }
+        assert_eq!(count(&layout, |cell| matches!(cell, CellType::Enemy(_))), 0);
+        assert_eq!(
+            count(&layout, |cell| matches!(cell, CellType::Weapon(_))),
+            1
+        );
     }
 
-    level_translation
-}
+    #[test]
+    fn spawn_counts_follow_difficulty() {
+        for seed in 0..100 {
+            let difficulty = Difficulty::Normal.step((seed % 6) as f32 * 0.2);
+            let mut rng = StdRng::seed_from_u64(seed);
+            let layout =
+                generate_normal_level(&StripGenerator, &difficulty, &mut rng, previus_door(seed));
+            assert_eq!(
+                count(&layout, |cell| matches!(cell, CellType::Enemy(_))),
+                difficulty.enemies as usize
+            );
+            assert_eq!(
+                count(&layout, |cell| matches!(cell, CellType::Weapon(_))),
+                LEVEL_WEAPON_SPAWNS as usize
+            );
+        }
+    }
+
+    #[test]
+    fn normal_levels_are_connected() {
+        for generator in [RoomGeneratorType::Strips, RoomGeneratorType::Wfc] {
+            for seed in 0..TEST_SEEDS {
+                let mut rng = StdRng::seed_from_u64(seed);
+                // the most crowded rooms are the hardest to connect
+                let difficulty = Difficulty::Nightmare.step((seed % 3) as f32 * 0.5);
+                let layout = generate_normal_level(
+                    generator.generator(),
+                    &difficulty,
+                    &mut rng,
+                    previus_door(seed),
+                );
+                assert_connected(&layout, seed);
+            }
+        }
+    }
 
-pub fn spawn_level_sun(level_type: LevelType, commands: &mut Commands) {
-    match level_type {
-        LevelType::Covered => {}
-        LevelType::Open(level_color) => {
-            // directional 'sun' light
-            commands.spawn((
-                DirectionalLightBundle {
-                    directional_light: DirectionalLight {
-                        shadows_enabled: true,
-                        color: level_color.into(),
-                        ..default()
-                    },
-                    transform: Transform {
-                        translation: Vec3::new(0.0, 2.0, 0.0),
-                        rotation: Quat::from_rotation_z(-std::f32::consts::FRAC_PI_4)
-                            * Quat::from_rotation_x(-std::f32::consts::FRAC_PI_4),
-                        ..default()
-                    },
-                    ..default()
-                },
-                LevelObject,
-            ));
+    #[test]
+    fn boss_levels_are_connected() {
+        for seed in 1..TEST_SEEDS {
+            let Some(door) = previus_door(seed) else {
+                continue;
+            };
+            assert_connected(&generate_boss_level(Some(door)), seed);
         }
     }
 }
@@ -1,5 +1,6 @@
 use bevy::{
     core_pipeline::Skybox,
+    ecs::system::SystemParam,
     prelude::*,
     render::render_resource::{TextureViewDescriptor, TextureViewDimension},
 };
@@ -12,22 +13,32 @@ use rand::{
 };
 
 use crate::{
-    enemies::{Enemy, EnemyAssets},
-    player::{Player, PlayerResources},
-    ui::UiResources,
+    difficulty::Difficulty,
+    enemies::{Boss, BossPhase, BossPhaseEvent, Enemy},
+    player::Player,
+    run::{RunConfig, RunMode, RunRng, RunSeed},
     utils::remove_all_with,
-    weapons::{Projectile, WeaponAssets},
-    GlobalState, COLLISION_GROUP_ENEMY, COLLISION_GROUP_LEVEL, COLLISION_GROUP_PLAYER,
-    COLLISION_GROUP_PROJECTILES,
+    weapons::Projectile,
+    GameSettings, GlobalState, COLLISION_GROUP_ENEMY, COLLISION_GROUP_LEVEL,
+    COLLISION_GROUP_PLAYER, COLLISION_GROUP_PROJECTILES,
 };
 
 use self::{
     door::Door,
-    generation::{spawn_level, spawn_level_sun},
+    generation::{
+        generate_authored_level, generate_boss_level, generate_normal_level, RoomGeneratorType,
+    },
+    room::{Room, RoomLoader},
+    spawn::{spawn_level, spawn_level_sun, LevelSpawnResources},
 };
 
 mod door;
-mod generation;
+mod editor;
+pub mod generation;
+pub mod navigation;
+pub mod room;
+mod spawn;
+mod wfc;
 
 const FLOOR_THICKNESS: f32 = 1.0;
 const LEVEL_SIZE: f32 = 200.0;
@@ -39,11 +50,14 @@ const FILL_AMOUNT: f32 = 0.02;
 const STRIP_LENGTH: u32 = 3;
 
 const LEVEL_WEAPON_SPAWNS: u32 = 4;
-const LEVEL_WEAPON_PISTOL_SPAWN_THRESHOLD: f64 = 0.3;
-const LEVEL_WEAPON_SHOTGUN_SPAWN_THRESHOLD: f64 = 0.6;
 
-const LEVEL_ENEMIES: u32 = 4;
-const LEVEL_SMALL_ENEMIES_PERCENT: f64 = 0.5;
+// Chance for a normal level to be one of the hand authored rooms
+const LEVEL_HAND_AUTHORED_CHANCE: f64 = 0.3;
+
+// Boss music plays faster in the later phases of the fight
+const LEVEL_BOSS_MUSIC_RATE_VOLLEYS: f64 = 1.05;
+const LEVEL_BOSS_MUSIC_RATE_SUMMON: f64 = 1.1;
+const LEVEL_BOSS_MUSIC_RATE_HAZARDS: f64 = 1.2;
 
 const LEVEL_LIGHTS_COVERAGE: f64 = 0.2;
 const LIGHT_SIZE: f32 = 1.0;
@@ -59,6 +73,9 @@ pub struct LevelPlugin;
 
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut App) {
+        app.init_asset::<Room>();
+        app.init_asset_loader::<RoomLoader>();
+
         app.add_collection_to_loading_state::<_, LevelAssets>(GlobalState::AssetLoading);
 
         app.add_event::<LevelStarted>();
@@ -66,6 +83,7 @@ impl Plugin for LevelPlugin {
         app.add_event::<LevelSwitch>();
 
         app.add_plugins(door::DoorPlugin);
+        app.add_plugins(editor::EditorPlugin);
 
         app.add_systems(
             OnTransition {
@@ -85,6 +103,21 @@ impl Plugin for LevelPlugin {
                 from: GlobalState::MainMenu,
                 to: GlobalState::InGame,
             },
+            (start_in_game_music, start_run),
+        );
+
+        app.add_systems(
+            OnTransition {
+                from: GlobalState::MainMenu,
+                to: GlobalState::Editor,
+            },
+            (remove_all_with::<LevelObject>, remove_all_with::<Player>),
+        );
+        app.add_systems(
+            OnTransition {
+                from: GlobalState::Editor,
+                to: GlobalState::InGame,
+            },
             start_in_game_music,
         );
 
@@ -174,6 +207,8 @@ impl Plugin for LevelPlugin {
             Update,
             (
                 continues_music,
+                boss_music_intensity,
+                level_switch_music,
                 level_progress,
                 level_switch,
                 level_delete_old,
@@ -201,6 +236,12 @@ pub struct LevelAssets {
     pub in_game: Handle<AudioSource>,
     #[asset(path = "dragon_lair.wav")]
     pub dragon_lair: Handle<AudioSource>,
+
+    #[asset(
+        paths("rooms/pillars.room", "rooms/arena.room", "rooms/crossroads.room"),
+        collection(typed)
+    )]
+    pub rooms: Vec<Handle<Room>>,
 }
 
 #[derive(Resource)]
@@ -281,11 +322,44 @@ pub enum LevelType {
 pub struct LevelInfo {
     pub finished: bool,
     pub level_type: LevelType,
-    pub game_progress: i32,
+    // Index of the current room in the run
+    pub room: i32,
+    pub room_generator: RoomGeneratorType,
+    pub run_mode: RunMode,
     pub translation: Vec3,
     pub old_level_objects: Vec<Entity>,
 }
 
+// Run settings picked in the menus
+#[derive(SystemParam)]
+pub struct RunSettings<'w> {
+    game_settings: Res<'w, GameSettings>,
+    run_mode: Res<'w, RunMode>,
+}
+
+impl RunSettings<'_> {
+    pub fn level_info(&self, room: i32) -> LevelInfo {
+        LevelInfo {
+            finished: false,
+            level_type: LevelType::Covered,
+            room,
+            room_generator: self.game_settings.room_generator,
+            run_mode: *self.run_mode,
+            translation: Vec3::ZERO,
+            old_level_objects: vec![],
+        }
+    }
+}
+
+// Everything that decides how the next room looks
+#[derive(SystemParam)]
+struct NextRoom<'w> {
+    rooms: Res<'w, Assets<Room>>,
+    difficulty: Res<'w, Difficulty>,
+    run_config: Res<'w, RunConfig>,
+    run_rng: ResMut<'w, RunRng>,
+}
+
 #[derive(Event)]
 pub struct LevelStarted;
 
@@ -450,6 +524,7 @@ fn resume_physics(mut physics: ResMut<RapierConfiguration>) {
 
 fn start_in_game_music(audio: Res<Audio>, level_assets: Res<LevelAssets>) {
     audio.play(level_assets.in_game.clone());
+    audio.set_playback_rate(1.0);
 }
 
 fn resume_music(audio: Res<Audio>) {
@@ -464,10 +539,37 @@ fn stop_music(audio: Res<Audio>) {
     audio.stop();
 }
 
-fn continues_music(audio: Res<Audio>, level_assets: Res<LevelAssets>, level_info: Res<LevelInfo>) {
+fn boss_music_rate(phase: BossPhase) -> f64 {
+    match phase {
+        BossPhase::Sweep => 1.0,
+        BossPhase::Volleys => LEVEL_BOSS_MUSIC_RATE_VOLLEYS,
+        BossPhase::Summon => LEVEL_BOSS_MUSIC_RATE_SUMMON,
+        BossPhase::Hazards => LEVEL_BOSS_MUSIC_RATE_HAZARDS,
+    }
+}
+
+fn boss_music_intensity(audio: Res<Audio>, mut boss_phase_events: EventReader<BossPhaseEvent>) {
+    for event in boss_phase_events.read() {
+        audio.set_playback_rate(boss_music_rate(event.phase));
+    }
+}
+
+fn continues_music(
+    audio: Res<Audio>,
+    level_assets: Res<LevelAssets>,
+    level_info: Res<LevelInfo>,
+    run_config: Res<RunConfig>,
+    bosses: Query<&Boss>,
+) {
     if !audio.is_playing_sound() {
-        if level_info.game_progress < 100 {
-            audio.play(level_assets.in_game.clone());
+        if run_config.is_boss_room(level_info.run_mode, level_info.room) {
+            audio.play(level_assets.dragon_lair.clone());
+            // keep the intensity of the current boss phase
+            let phase = bosses
+                .iter()
+                .next()
+                .map_or(BossPhase::Sweep, |boss| boss.phase);
+            audio.set_playback_rate(boss_music_rate(phase));
         } else {
             audio.play(level_assets.in_game.clone());
         }
@@ -475,40 +577,49 @@ fn continues_music(audio: Res<Audio>, level_assets: Res<LevelAssets>, level_info
 }
 
 fn spawn_initial_level(
-    ui_resources: Res<UiResources>,
-    level_assets: Res<LevelAssets>,
-    enemy_assets: Res<EnemyAssets>,
-    weapon_assets: Res<WeaponAssets>,
-    level_resources: Res<LevelResources>,
-    player_resources: Res<PlayerResources>,
+    spawn_resources: LevelSpawnResources,
+    run_settings: RunSettings,
+    difficulty: Res<Difficulty>,
+    mut run_seed: ResMut<RunSeed>,
     mut commands: Commands,
 ) {
+    let mut run_rng = run_seed.new_run();
+
+    let level_info = run_settings.level_info(RunConfig::TUTORIAL_ROOM);
+    let difficulty = difficulty.step(0.0);
+    let mut layout = generate_normal_level(
+        level_info.room_generator.generator(),
+        &difficulty,
+        &mut run_rng.0,
+        None,
+    );
+    layout.carve_tutorial();
+
     spawn_level(
-        ui_resources.as_ref(),
-        level_assets.as_ref(),
-        enemy_assets.as_ref(),
-        weapon_assets.as_ref(),
-        level_resources.as_ref(),
-        player_resources.as_ref(),
+        &spawn_resources,
         &mut commands,
+        &layout,
+        &difficulty,
         Vec3::ZERO,
         None,
         LevelType::Covered,
-        true,
-        false,
     );
 
-    commands.insert_resource(LevelInfo {
-        finished: false,
-        level_type: LevelType::Covered,
-        game_progress: -10,
-        translation: Vec3::ZERO,
-        old_level_objects: vec![],
-    });
+    commands.insert_resource(level_info);
+    commands.insert_resource(run_rng);
+    commands.insert_resource(run_seed.combat_rng());
+}
+
+// Level behind the main menu is spawned before the player
+// picks the run settings, so they are applied when the run starts
+fn start_run(run_settings: RunSettings, mut level_info: ResMut<LevelInfo>) {
+    level_info.run_mode = *run_settings.run_mode;
+    level_info.room_generator = run_settings.game_settings.room_generator;
 }
 
 fn level_progress(
     enemies: Query<Entity, With<Enemy>>,
+    run_config: Res<RunConfig>,
     mut level_info: ResMut<LevelInfo>,
     mut level_started_events: EventReader<LevelStarted>,
     mut level_finished_events: EventWriter<LevelFinished>,
@@ -518,13 +629,15 @@ fn level_progress(
         level_info.finished = false;
     }
 
+    // minions of spawners are enemies too, so the room
+    // is not finished while a spawner can still make more
     let remaining_enemies = enemies.iter().count();
     if remaining_enemies == 0 && !level_info.finished {
         level_info.finished = true;
-        level_info.game_progress += 10;
+        level_info.room += 1;
 
         // if boss level is finished
-        if 100 < level_info.game_progress {
+        if run_config.is_won(level_info.run_mode, level_info.room) {
             global_state.set(GlobalState::GameWon);
             return;
         }
@@ -533,29 +646,44 @@ fn level_progress(
     }
 }
 
-fn level_switch(
+fn level_switch_music(
     audio: Res<Audio>,
-    ui_resources: Res<UiResources>,
     level_assets: Res<LevelAssets>,
-    enemy_assets: Res<EnemyAssets>,
-    weapon_assets: Res<WeaponAssets>,
-    level_resources: Res<LevelResources>,
-    player_resources: Res<PlayerResources>,
+    level_info: Res<LevelInfo>,
+    run_config: Res<RunConfig>,
+    mut level_switch_events: EventReader<LevelSwitch>,
+) {
+    for _ in level_switch_events.read() {
+        let boss_level = run_config.is_boss_room(level_info.run_mode, level_info.room);
+        let after_boss_level = run_config.is_boss_room(level_info.run_mode, level_info.room - 1);
+        if boss_level {
+            audio.stop();
+            audio.play(level_assets.dragon_lair.clone());
+            audio.set_playback_rate(1.0);
+        } else if after_boss_level {
+            audio.stop();
+            audio.play(level_assets.in_game.clone());
+            audio.set_playback_rate(1.0);
+        }
+    }
+}
+
+fn level_switch(
+    spawn_resources: LevelSpawnResources,
+    mut next_room: NextRoom,
     level_objects: Query<Entity, With<LevelObject>>,
     mut skybox: Query<&mut Skybox>,
     mut level_info: ResMut<LevelInfo>,
     mut commands: Commands,
     mut level_switch_events: EventReader<LevelSwitch>,
 ) {
+    let run_config = next_room.run_config.as_ref();
+    let run_rng = &mut next_room.run_rng;
     for event in level_switch_events.read() {
         let old_level_objects = level_objects.iter().collect::<Vec<_>>();
 
-        let boss_level = level_info.game_progress == 100;
-        let pre_boss_level = level_info.game_progress == 90;
-        if boss_level {
-            audio.stop();
-            audio.play(level_assets.dragon_lair.clone());
-        }
+        let boss_level = run_config.is_boss_room(level_info.run_mode, level_info.room);
+        let pre_boss_level = run_config.is_pre_boss_room(level_info.run_mode, level_info.room);
 
         let new_level_type = if boss_level {
             LevelType::Open(LevelColor::Normal)
@@ -565,10 +693,10 @@ fn level_switch(
             match level_info.level_type {
                 LevelType::Open(_) => LevelType::Covered,
                 LevelType::Covered => {
-                    if rand::thread_rng().gen_ratio(1, 3) {
+                    if run_rng.0.gen_ratio(1, 3) {
                         LevelType::Covered
                     } else {
-                        let level_color = rand::random::<LevelColor>();
+                        let level_color = run_rng.0.gen::<LevelColor>();
                         LevelType::Open(level_color)
                     }
                 }
@@ -580,24 +708,48 @@ fn level_switch(
             match new_level_type {
                 LevelType::Covered => {}
                 LevelType::Open(level_color) => {
-                    skybox.0 = level_color.skybox_image(level_assets.as_ref());
+                    skybox.0 = level_color.skybox_image(&spawn_resources.level_assets);
                 }
             }
         }
 
+        let difficulty = next_room
+            .difficulty
+            .step(run_config.progress(level_info.room));
+
+        let authored_room = if !boss_level
+            && !pre_boss_level
+            && !spawn_resources.level_assets.rooms.is_empty()
+            && run_rng.0.gen_bool(LEVEL_HAND_AUTHORED_CHANCE)
+        {
+            let rooms = &spawn_resources.level_assets.rooms;
+            let index = run_rng.0.gen_range(0..rooms.len());
+            next_room.rooms.get(&rooms[index])
+        } else {
+            None
+        };
+
+        let layout = if boss_level {
+            generate_boss_level(Some(event.exit_door))
+        } else if let Some(room) = authored_room {
+            generate_authored_level(&room.layout, event.exit_door)
+        } else {
+            generate_normal_level(
+                level_info.room_generator.generator(),
+                &difficulty,
+                &mut run_rng.0,
+                Some(event.exit_door),
+            )
+        };
+
         let new_translation = spawn_level(
-            ui_resources.as_ref(),
-            level_assets.as_ref(),
-            enemy_assets.as_ref(),
-            weapon_assets.as_ref(),
-            level_resources.as_ref(),
-            player_resources.as_ref(),
+            &spawn_resources,
             &mut commands,
+            &layout,
+            &difficulty,
             level_info.translation,
             Some(event.exit_door),
             new_level_type,
-            false,
-            boss_level,
         );
 
         level_info.level_type = new_level_type;
@@ -0,0 +1,157 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use bevy::prelude::*;
+
+use super::{
+    generation::{CellType, LevelLayout},
+    COLUMN_SIZE, GRID_SIZE, LEVEL_SIZE,
+};
+
+// Costs are scaled by 10 to keep them integer
+const NAV_STRAIGHT_COST: u32 = 10;
+const NAV_DIAGONAL_COST: u32 = 14;
+// Cache is cleared when it grows above this
+const NAV_CACHE_SIZE: usize = 256;
+
+const NAV_NEIGHBOURS: [(i32, i32); 8] = [
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+// (x, y) position in the level grid
+pub type NavCell = (usize, usize);
+
+// Walkable cells of the current room. Enemies use it
+// to find paths around columns. Paths are cached until
+// the next room replaces the grid.
+#[derive(Resource)]
+pub struct NavGrid {
+    // row order
+    walkable: [[bool; GRID_SIZE]; GRID_SIZE],
+    translation: Vec3,
+    paths: HashMap<(NavCell, NavCell), Option<Vec<NavCell>>>,
+}
+
+impl NavGrid {
+    pub fn new(layout: &LevelLayout, translation: Vec3) -> Self {
+        let walkable = layout
+            .grid
+            .map(|row| row.map(|cell| !matches!(cell, CellType::Column | CellType::Door(_))));
+        Self {
+            walkable,
+            translation,
+            paths: HashMap::new(),
+        }
+    }
+
+    pub fn world_to_cell(&self, position: Vec2) -> Option<NavCell> {
+        let local = position - self.translation.xy();
+        let x = ((local.x + LEVEL_SIZE / 2.0) / COLUMN_SIZE).floor();
+        let y = ((LEVEL_SIZE / 2.0 - local.y) / COLUMN_SIZE).floor();
+        if x < 0.0 || GRID_SIZE as f32 <= x || y < 0.0 || GRID_SIZE as f32 <= y {
+            return None;
+        }
+        Some((x as usize, y as usize))
+    }
+
+    // Center of the cell in world space
+    pub fn cell_to_world(&self, cell: NavCell) -> Vec2 {
+        let x = (-LEVEL_SIZE / 2.0) + COLUMN_SIZE * cell.0 as f32 + COLUMN_SIZE / 2.0;
+        let y = (LEVEL_SIZE / 2.0) - COLUMN_SIZE * cell.1 as f32 - COLUMN_SIZE / 2.0;
+        Vec2::new(x, y) + self.translation.xy()
+    }
+
+    // Returns centers of cells to walk through to get from
+    // the `start` to the `goal`. `start` itself is not included.
+    pub fn find_path(&mut self, start: NavCell, goal: NavCell) -> Option<Vec<Vec2>> {
+        if NAV_CACHE_SIZE < self.paths.len() {
+            self.paths.clear();
+        }
+        let walkable = &self.walkable;
+        let path = self
+            .paths
+            .entry((start, goal))
+            .or_insert_with(|| a_star(walkable, start, goal))
+            .clone()?;
+        Some(path.iter().map(|cell| self.cell_to_world(*cell)).collect())
+    }
+}
+
+fn heuristic(a: NavCell, b: NavCell) -> u32 {
+    let dx = a.0.abs_diff(b.0) as u32;
+    let dy = a.1.abs_diff(b.1) as u32;
+    NAV_STRAIGHT_COST * dx.max(dy) + (NAV_DIAGONAL_COST - NAV_STRAIGHT_COST) * dx.min(dy)
+}
+
+// Start and goal cells are always considered walkable,
+// so enemies pushed into a column or a player standing
+// in a doorway still get a path.
+fn a_star(
+    walkable: &[[bool; GRID_SIZE]; GRID_SIZE],
+    start: NavCell,
+    goal: NavCell,
+) -> Option<Vec<NavCell>> {
+    let is_walkable = |cell: NavCell| cell == start || cell == goal || walkable[cell.1][cell.0];
+
+    let mut costs = [[u32::MAX; GRID_SIZE]; GRID_SIZE];
+    let mut came_from = [[None::<NavCell>; GRID_SIZE]; GRID_SIZE];
+    let mut open = BinaryHeap::new();
+
+    costs[start.1][start.0] = 0;
+    open.push(Reverse((heuristic(start, goal), start)));
+
+    while let Some(Reverse((_, cell))) = open.pop() {
+        if cell == goal {
+            let mut path = vec![];
+            let mut current = goal;
+            while current != start {
+                path.push(current);
+                current = came_from[current.1][current.0]?;
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        let cost = costs[cell.1][cell.0];
+        for (x_mod, y_mod) in NAV_NEIGHBOURS.iter() {
+            let (next_x, next_y) = (cell.0 as i32 + x_mod, cell.1 as i32 + y_mod);
+            if next_x < 0 || GRID_SIZE as i32 <= next_x || next_y < 0 || GRID_SIZE as i32 <= next_y
+            {
+                continue;
+            }
+            let next = (next_x as usize, next_y as usize);
+            if !is_walkable(next) {
+                continue;
+            }
+
+            let diagonal = *x_mod != 0 && *y_mod != 0;
+            // do not cut corners of columns
+            if diagonal && !(is_walkable((next.0, cell.1)) && is_walkable((cell.0, next.1))) {
+                continue;
+            }
+
+            let next_cost = cost
+                + if diagonal {
+                    NAV_DIAGONAL_COST
+                } else {
+                    NAV_STRAIGHT_COST
+                };
+            if next_cost < costs[next.1][next.0] {
+                costs[next.1][next.0] = next_cost;
+                came_from[next.1][next.0] = Some(cell);
+                open.push(Reverse((next_cost + heuristic(next, goal), next)));
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,367 @@
+use std::fmt::Display;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+
+use crate::{enemies::EnemyType, weapons::WeaponType};
+
+use super::{
+    door::{Door, DoorState, DoorType},
+    generation::{CellType, LevelLayout},
+    GRID_SIZE,
+};
+
+// Hand authored room. It is stored as a plain text
+// file with one character per cell:
+// `.` - empty
+// `#` - column
+// `T`, `B`, `L`, `R` - top, bottom, left and right doors
+// `*` - light
+// `p`, `s`, `m` - pistol, shotgun and minigun
+// other letters and digits - enemy with that id
+// `@` - player
+// Room must be `GRID_SIZE` x `GRID_SIZE` cells, be surrounded
+// by columns and have at least one door on each side.
+#[derive(Debug, Asset, TypePath)]
+pub struct Room {
+    pub layout: LevelLayout,
+}
+
+#[derive(Debug)]
+pub enum RoomError {
+    Io(std::io::Error),
+    NotUtf8,
+    WrongHeight {
+        height: usize,
+    },
+    WrongWidth {
+        line: usize,
+        width: usize,
+    },
+    UnknownCell {
+        line: usize,
+        column: usize,
+        cell: char,
+    },
+    OpenBorder {
+        line: usize,
+        column: usize,
+    },
+    MisplacedDoor {
+        line: usize,
+        column: usize,
+        door: char,
+    },
+    MissingDoor {
+        door: char,
+    },
+}
+
+impl Display for RoomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RoomError::Io(e) => write!(f, "could not read room: {e}"),
+            RoomError::NotUtf8 => write!(f, "room is not a valid utf8 text"),
+            RoomError::WrongHeight { height } => {
+                write!(f, "room has {height} lines, expected {GRID_SIZE}")
+            }
+            RoomError::WrongWidth { line, width } => {
+                write!(f, "{line}: line has {width} cells, expected {GRID_SIZE}")
+            }
+            RoomError::UnknownCell { line, column, cell } => {
+                write!(f, "{line}:{column}: unknown cell '{cell}'")
+            }
+            RoomError::OpenBorder { line, column } => {
+                write!(f, "{line}:{column}: border cell must be a column or a door")
+            }
+            RoomError::MisplacedDoor { line, column, door } => {
+                write!(
+                    f,
+                    "{line}:{column}: door '{door}' is not on its side of the room"
+                )
+            }
+            RoomError::MissingDoor { door } => write!(f, "room has no '{door}' door"),
+        }
+    }
+}
+
+impl std::error::Error for RoomError {}
+
+impl From<std::io::Error> for RoomError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+#[derive(Default)]
+pub struct RoomLoader;
+
+impl AssetLoader for RoomLoader {
+    type Asset = Room;
+    type Settings = ();
+    type Error = RoomError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let text = std::str::from_utf8(&bytes).map_err(|_| RoomError::NotUtf8)?;
+            let layout = parse_room(text)?;
+            Ok(Room { layout })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["room"]
+    }
+}
+
+pub fn parse_room(text: &str) -> Result<LevelLayout, RoomError> {
+    let lines = text.trim_end().lines().collect::<Vec<_>>();
+    if lines.len() != GRID_SIZE {
+        return Err(RoomError::WrongHeight {
+            height: lines.len(),
+        });
+    }
+
+    let mut grid = [[CellType::Empty; GRID_SIZE]; GRID_SIZE];
+    for (y, line) in lines.iter().enumerate() {
+        let cells = line.trim_end().chars().collect::<Vec<_>>();
+        if cells.len() != GRID_SIZE {
+            return Err(RoomError::WrongWidth {
+                line: y + 1,
+                width: cells.len(),
+            });
+        }
+
+        for (x, c) in cells.into_iter().enumerate() {
+            let (line, column) = (y + 1, x + 1);
+            let on_border = x == 0 || x == GRID_SIZE - 1 || y == 0 || y == GRID_SIZE - 1;
+            let corner = (x == 0 || x == GRID_SIZE - 1) && (y == 0 || y == GRID_SIZE - 1);
+
+            let door = |door_type: DoorType, on_side: bool, grid_pos: usize| {
+                if on_side && !corner {
+                    Ok(CellType::Door(Door {
+                        door_type,
+                        door_state: DoorState::Locked,
+                        grid_pos,
+                    }))
+                } else {
+                    Err(RoomError::MisplacedDoor {
+                        line,
+                        column,
+                        door: c,
+                    })
+                }
+            };
+
+            let cell = match c {
+                '.' => CellType::Empty,
+                '#' => CellType::Column,
+                '*' => CellType::Light,
+                'p' => CellType::Weapon(WeaponType::Pistol),
+                's' => CellType::Weapon(WeaponType::Shotgun),
+                'm' => CellType::Weapon(WeaponType::Minigun),
+                '@' => CellType::Player,
+                'T' => door(DoorType::Top, y == 0, x)?,
+                'B' => door(DoorType::Bottom, y == GRID_SIZE - 1, x)?,
+                'L' => door(DoorType::Left, x == 0, y)?,
+                'R' => door(DoorType::Right, x == GRID_SIZE - 1, y)?,
+                cell => match EnemyType::from_char(cell) {
+                    Some(enemy_type) => CellType::Enemy(enemy_type),
+                    None => return Err(RoomError::UnknownCell { line, column, cell }),
+                },
+            };
+
+            if on_border && !matches!(cell, CellType::Column | CellType::Door(_)) {
+                return Err(RoomError::OpenBorder { line, column });
+            }
+
+            grid[y][x] = cell;
+        }
+    }
+
+    for (door_type, door) in [
+        (DoorType::Top, 'T'),
+        (DoorType::Bottom, 'B'),
+        (DoorType::Left, 'L'),
+        (DoorType::Right, 'R'),
+    ] {
+        let has_door = grid.iter().flatten().any(|cell| match cell {
+            CellType::Door(d) => d.door_type == door_type,
+            _ => false,
+        });
+        if !has_door {
+            return Err(RoomError::MissingDoor { door });
+        }
+    }
+
+    Ok(LevelLayout { grid })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Empty room with a door in the middle of each side
+    fn room() -> Vec<Vec<char>> {
+        let middle = GRID_SIZE / 2;
+        let mut cells = vec![vec!['.'; GRID_SIZE]; GRID_SIZE];
+        for (y, line) in cells.iter_mut().enumerate() {
+            for (x, cell) in line.iter_mut().enumerate() {
+                if x == 0 || x == GRID_SIZE - 1 || y == 0 || y == GRID_SIZE - 1 {
+                    *cell = '#';
+                }
+            }
+        }
+        cells[0][middle] = 'T';
+        cells[GRID_SIZE - 1][middle] = 'B';
+        cells[middle][0] = 'L';
+        cells[middle][GRID_SIZE - 1] = 'R';
+        cells
+    }
+
+    fn text(cells: &[Vec<char>]) -> String {
+        cells
+            .iter()
+            .map(|line| line.iter().collect::<String>() + "\n")
+            .collect()
+    }
+
+    #[test]
+    fn valid_room_is_parsed() {
+        let mut cells = room();
+        cells[3][4] = '1';
+        cells[5][6] = 'm';
+        cells[7][8] = '@';
+        let layout = parse_room(&text(&cells)).unwrap();
+
+        assert_eq!(layout.grid[3][4], CellType::Enemy(EnemyType('1')));
+        assert_eq!(layout.grid[5][6], CellType::Weapon(WeaponType::Minigun));
+        assert_eq!(layout.grid[7][8], CellType::Player);
+        assert_eq!(
+            layout.grid[0][GRID_SIZE / 2],
+            CellType::Door(Door {
+                door_type: DoorType::Top,
+                door_state: DoorState::Locked,
+                grid_pos: GRID_SIZE / 2,
+            })
+        );
+    }
+
+    #[test]
+    fn wrong_height() {
+        let mut cells = room();
+        cells.remove(3);
+        let error = parse_room(&text(&cells)).unwrap_err();
+        assert!(matches!(error, RoomError::WrongHeight { height } if height == GRID_SIZE - 1));
+    }
+
+    #[test]
+    fn wrong_width() {
+        let mut cells = room();
+        cells[4].push('.');
+        let error = parse_room(&text(&cells)).unwrap_err();
+        assert!(matches!(
+            error,
+            RoomError::WrongWidth { line: 5, width } if width == GRID_SIZE + 1
+        ));
+    }
+
+    #[test]
+    fn unknown_cell() {
+        let mut cells = room();
+        cells[4][3] = '?';
+        let error = parse_room(&text(&cells)).unwrap_err();
+        assert!(matches!(
+            error,
+            RoomError::UnknownCell {
+                line: 5,
+                column: 4,
+                cell: '?'
+            }
+        ));
+        // enemy ids can not be characters of other cells
+        cells[4][3] = 'T';
+        let error = parse_room(&text(&cells)).unwrap_err();
+        assert!(matches!(
+            error,
+            RoomError::MisplacedDoor {
+                line: 5,
+                column: 4,
+                door: 'T'
+            }
+        ));
+    }
+
+    #[test]
+    fn open_border() {
+        let mut cells = room();
+        cells[0][2] = '.';
+        let error = parse_room(&text(&cells)).unwrap_err();
+        assert!(matches!(
+            error,
+            RoomError::OpenBorder { line: 1, column: 3 }
+        ));
+    }
+
+    #[test]
+    fn misplaced_door() {
+        let mut cells = room();
+        cells[0][2] = 'L';
+        let error = parse_room(&text(&cells)).unwrap_err();
+        assert!(matches!(
+            error,
+            RoomError::MisplacedDoor {
+                line: 1,
+                column: 3,
+                door: 'L'
+            }
+        ));
+
+        // doors can not be in the corners
+        let mut cells = room();
+        cells[0][0] = 'T';
+        let error = parse_room(&text(&cells)).unwrap_err();
+        assert!(matches!(
+            error,
+            RoomError::MisplacedDoor {
+                line: 1,
+                column: 1,
+                door: 'T'
+            }
+        ));
+    }
+
+    #[test]
+    fn missing_door() {
+        let mut cells = room();
+        cells[GRID_SIZE / 2][GRID_SIZE - 1] = '#';
+        let error = parse_room(&text(&cells)).unwrap_err();
+        assert!(matches!(error, RoomError::MissingDoor { door: 'R' }));
+    }
+
+    // Editor saves rooms with the `Display` of the layout
+    #[test]
+    fn saved_room_is_loaded_back() {
+        let mut cells = room();
+        cells[3][4] = '5';
+        cells[5][6] = 's';
+        cells[7][8] = '*';
+        cells[9][10] = '#';
+        cells[11][12] = '@';
+        let layout = parse_room(&text(&cells)).unwrap();
+
+        let saved = layout.to_string();
+        assert_eq!(saved, text(&cells));
+        assert_eq!(parse_room(&saved).unwrap(), layout);
+    }
+}
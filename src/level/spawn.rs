@@ -0,0 +1,172 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    difficulty::DifficultyStep,
+    enemies::{spawn_enemy, EnemyDefinitions},
+    player::{spawn_player, PlayerResources},
+    ui::UiResources,
+    weapons::{spawn_weapon, WeaponAssets},
+};
+
+use super::{
+    door::{spawn_door, Door, DoorType},
+    generation::{CellType, LevelLayout},
+    navigation::NavGrid,
+    spawn_light, LevelAssets, LevelColliderBundle, LevelObject, LevelResources, LevelType,
+    COLUMN_HIGHT, COLUMN_SIZE, FLOOR_THICKNESS, LEVEL_SIZE,
+};
+
+// Assets of everything a level can be made of
+#[derive(SystemParam)]
+pub struct LevelSpawnResources<'w> {
+    pub ui_resources: Res<'w, UiResources>,
+    pub level_assets: Res<'w, LevelAssets>,
+    pub enemy_definitions: Res<'w, EnemyDefinitions>,
+    pub weapon_assets: Res<'w, WeaponAssets>,
+    pub level_resources: Res<'w, LevelResources>,
+    pub player_resources: Res<'w, PlayerResources>,
+}
+
+pub fn spawn_level(
+    resources: &LevelSpawnResources,
+    commands: &mut Commands,
+    layout: &LevelLayout,
+    difficulty: &DifficultyStep,
+    level_translation: Vec3,
+    previus_door: Option<Door>,
+    level_type: LevelType,
+) -> Vec3 {
+    debug!("Spawning level:\n{layout}");
+
+    let LevelSpawnResources {
+        ui_resources,
+        level_assets,
+        enemy_definitions,
+        weapon_assets,
+        level_resources,
+        player_resources,
+    } = resources;
+
+    let level_translation = match previus_door {
+        Some(door) => match door.door_type {
+            DoorType::Top => level_translation + Vec3::new(0.0, LEVEL_SIZE, 0.0),
+            DoorType::Bottom => level_translation + Vec3::new(0.0, -LEVEL_SIZE, 0.0),
+            DoorType::Left => level_translation + Vec3::new(-LEVEL_SIZE, 0.0, 0.0),
+            DoorType::Right => level_translation + Vec3::new(LEVEL_SIZE, 0.0, 0.0),
+        },
+        None => level_translation,
+    };
+
+    for (y, row) in layout.grid.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            let x_pos = (-LEVEL_SIZE / 2.0) + COLUMN_SIZE * x as f32 + COLUMN_SIZE / 2.0;
+            let y_pos = (LEVEL_SIZE / 2.0) - COLUMN_SIZE * y as f32 - COLUMN_SIZE / 2.0;
+            let z_pos = COLUMN_HIGHT / 2.0;
+            let translation = Vec3::new(x_pos, y_pos, z_pos);
+            let transform = Transform::from_translation(translation + level_translation);
+
+            match cell {
+                CellType::Door(door) => {
+                    spawn_door(level_resources, commands, transform, *door);
+                }
+                CellType::Column => {
+                    commands.spawn(LevelColliderBundle::new(
+                        level_resources.column_mesh.clone(),
+                        level_resources.column_material.clone(),
+                        transform,
+                        Collider::cuboid(COLUMN_SIZE / 2.0, COLUMN_SIZE / 2.0, COLUMN_HIGHT / 2.0),
+                    ));
+                }
+                CellType::Light => {
+                    if level_type == LevelType::Covered {
+                        let mut light_transform = transform;
+                        light_transform.translation.z = COLUMN_HIGHT;
+                        spawn_light(level_resources, commands, light_transform);
+                    }
+                }
+                CellType::Weapon(weapon_type) => {
+                    spawn_weapon(
+                        weapon_assets,
+                        *weapon_type,
+                        difficulty.ammo,
+                        commands,
+                        transform,
+                    );
+                }
+                CellType::Enemy(enemy_type) => {
+                    spawn_enemy(
+                        enemy_definitions,
+                        weapon_assets,
+                        *enemy_type,
+                        difficulty,
+                        commands,
+                        transform,
+                    );
+                }
+                CellType::Player => {
+                    // we spanw player only once, so we can give him
+                    // some default skybox
+                    spawn_player(
+                        ui_resources,
+                        player_resources,
+                        level_assets.normal_skybox.clone(),
+                        commands,
+                        transform,
+                    );
+                }
+                CellType::Empty => {}
+            }
+        }
+    }
+
+    commands.insert_resource(NavGrid::new(layout, level_translation));
+
+    // floor
+    commands.spawn(LevelColliderBundle::new(
+        level_resources.floor_mesh.clone(),
+        level_resources.floor_material.clone(),
+        Transform::from_translation(level_translation),
+        Collider::cuboid(LEVEL_SIZE / 2.0, LEVEL_SIZE / 2.0, 0.5),
+    ));
+
+    // roof
+    if level_type == LevelType::Covered {
+        let mut roof_translation = level_translation;
+        roof_translation.z += COLUMN_HIGHT + FLOOR_THICKNESS / 2.0;
+        commands.spawn(LevelColliderBundle::new(
+            level_resources.floor_mesh.clone(),
+            level_resources.floor_material.clone(),
+            Transform::from_translation(roof_translation),
+            Collider::cuboid(LEVEL_SIZE / 2.0, LEVEL_SIZE / 2.0, 0.5),
+        ));
+    }
+
+    level_translation
+}
+
+pub fn spawn_level_sun(level_type: LevelType, commands: &mut Commands) {
+    match level_type {
+        LevelType::Covered => {}
+        LevelType::Open(level_color) => {
+            // directional 'sun' light
+            commands.spawn((
+                DirectionalLightBundle {
+                    directional_light: DirectionalLight {
+                        shadows_enabled: true,
+                        color: level_color.into(),
+                        ..default()
+                    },
+                    transform: Transform {
+                        translation: Vec3::new(0.0, 2.0, 0.0),
+                        rotation: Quat::from_rotation_z(-std::f32::consts::FRAC_PI_4)
+                            * Quat::from_rotation_x(-std::f32::consts::FRAC_PI_4),
+                        ..default()
+                    },
+                    ..default()
+                },
+                LevelObject,
+            ));
+        }
+    }
+}
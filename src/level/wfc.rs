@@ -0,0 +1,254 @@
+use rand::{Rng, RngCore};
+
+use super::{
+    generation::{CellType, RoomGenerator, StripGenerator},
+    GRID_SIZE,
+};
+
+// Tiles are placed inside the level leaving 2 cells
+// near the border empty, same as other generators.
+const WFC_TILE_SIZE: usize = 4;
+const WFC_GRID_SIZE: usize = (GRID_SIZE - 4) / WFC_TILE_SIZE;
+const WFC_MAX_ATTEMPTS: u32 = 10;
+
+// Base tiles. All rotations of each tile are used.
+// Weights are for corridors, halls and arenas themes.
+const WFC_TILES: [([&str; WFC_TILE_SIZE], [f32; 3]); 10] = [
+    // Empty
+    (["....", "....", "....", "...."], [1.0, 6.0, 12.0]),
+    // Pillar
+    (["....", ".##.", ".##.", "...."], [0.5, 6.0, 1.0]),
+    // Solid
+    (["####", "####", "####", "####"], [6.0, 0.5, 1.0]),
+    // Corridor
+    (["####", "....", "....", "####"], [4.0, 0.2, 0.05]),
+    // Corridor turn
+    (["####", "#...", "#...", "#..#"], [2.0, 0.1, 0.05]),
+    // Corridor split
+    (["####", "....", "....", "#..#"], [1.5, 0.1, 0.05]),
+    // Corridor cross
+    (["#..#", "....", "....", "#..#"], [1.0, 0.5, 0.3]),
+    // Wall side
+    (["####", "####", "....", "...."], [1.0, 0.5, 2.0]),
+    // Wall outer corner
+    (["####", "####", "##..", "##.."], [0.5, 0.3, 1.0]),
+    // Wall inner corner
+    (["##..", "....", "....", "...."], [0.5, 0.3, 1.0]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WfcTheme {
+    Corridors,
+    Halls,
+    Arenas,
+}
+
+// Directions to the neighbour tile: right, down, left, up
+const WFC_DIRECTIONS: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+type Pattern = [[bool; WFC_TILE_SIZE]; WFC_TILE_SIZE];
+
+struct Tile {
+    pattern: Pattern,
+    weight: f32,
+}
+
+impl Tile {
+    // Edges in the same order as directions.
+    // Vertical edges are read top to bottom and
+    // horizontal ones left to right, so touching
+    // edges of neighbours must be equal.
+    fn edge(&self, direction: usize) -> [bool; WFC_TILE_SIZE] {
+        let p = &self.pattern;
+        let last = WFC_TILE_SIZE - 1;
+        std::array::from_fn(|i| match direction {
+            0 => p[i][last],
+            1 => p[last][i],
+            2 => p[i][0],
+            _ => p[0][i],
+        })
+    }
+}
+
+fn rotate(pattern: &Pattern) -> Pattern {
+    std::array::from_fn(|y| std::array::from_fn(|x| pattern[WFC_TILE_SIZE - 1 - x][y]))
+}
+
+fn theme_tiles(theme: WfcTheme) -> Vec<Tile> {
+    let mut tiles: Vec<Tile> = vec![];
+    for (rows, weights) in WFC_TILES.iter() {
+        let mut pattern: Pattern =
+            std::array::from_fn(|y| std::array::from_fn(|x| rows[y].as_bytes()[x] == b'#'));
+        for _ in 0..4 {
+            if tiles.iter().all(|tile| tile.pattern != pattern) {
+                tiles.push(Tile {
+                    pattern,
+                    weight: weights[theme as usize],
+                });
+            }
+            pattern = rotate(&pattern);
+        }
+    }
+    tiles
+}
+
+// Tile based Wave Function Collapse. Level is split into
+// 4x4 tiles and tiles are picked so that touching
+// edges of all neighbours match. Each level picks a
+// theme which changes how often each tile is used.
+pub struct WfcGenerator;
+
+impl RoomGenerator for WfcGenerator {
+    fn generate_walls(&self, rng: &mut dyn RngCore, grid: &mut [[CellType; GRID_SIZE]; GRID_SIZE]) {
+        let theme = match rng.gen_range(0..3) {
+            0 => WfcTheme::Corridors,
+            1 => WfcTheme::Halls,
+            _ => WfcTheme::Arenas,
+        };
+        let tiles = theme_tiles(theme);
+
+        for _ in 0..WFC_MAX_ATTEMPTS {
+            let Some(wave) = collapse(rng, &tiles) else {
+                continue;
+            };
+            for (tile_y, row) in wave.iter().enumerate() {
+                for (tile_x, tile) in row.iter().enumerate() {
+                    let pattern = &tiles[*tile].pattern;
+                    for (y, pattern_row) in pattern.iter().enumerate() {
+                        for (x, column) in pattern_row.iter().enumerate() {
+                            if *column {
+                                let grid_x = 2 + tile_x * WFC_TILE_SIZE + x;
+                                let grid_y = 2 + tile_y * WFC_TILE_SIZE + y;
+                                grid[grid_y][grid_x] = CellType::Column;
+                            }
+                        }
+                    }
+                }
+            }
+            return;
+        }
+
+        // could not find any valid combination
+        StripGenerator.generate_walls(rng, grid);
+    }
+}
+
+// Returns index of the tile for every position or
+// None if there was a contradiction.
+fn collapse(
+    rng: &mut dyn RngCore,
+    tiles: &[Tile],
+) -> Option<[[usize; WFC_GRID_SIZE]; WFC_GRID_SIZE]> {
+    // compatible[direction][a][b] is true if tile `b` can be
+    // placed next to the tile `a` in the `direction`
+    let compatible = (0..WFC_DIRECTIONS.len())
+        .map(|direction| {
+            let opposite = (direction + 2) % WFC_DIRECTIONS.len();
+            tiles
+                .iter()
+                .map(|a| {
+                    tiles
+                        .iter()
+                        .map(|b| a.edge(direction) == b.edge(opposite))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut wave = vec![vec![vec![true; tiles.len()]; WFC_GRID_SIZE]; WFC_GRID_SIZE];
+
+    loop {
+        // find not collapsed positions with the least options
+        let mut min_options = usize::MAX;
+        let mut candidates = vec![];
+        for (y, row) in wave.iter().enumerate() {
+            for (x, options) in row.iter().enumerate() {
+                let num_options = options.iter().filter(|o| **o).count();
+                if num_options <= 1 {
+                    continue;
+                }
+                if num_options < min_options {
+                    min_options = num_options;
+                    candidates.clear();
+                }
+                if num_options == min_options {
+                    candidates.push((x, y));
+                }
+            }
+        }
+
+        if candidates.is_empty() {
+            break;
+        }
+
+        let (x, y) = candidates[rng.gen_range(0..candidates.len())];
+        let options = &mut wave[y][x];
+        let total = tiles
+            .iter()
+            .zip(options.iter())
+            .filter(|(_, o)| **o)
+            .map(|(t, _)| t.weight)
+            .sum::<f32>();
+        let mut random = rng.gen_range(0.0..total);
+        let mut selected = 0;
+        for (i, tile) in tiles.iter().enumerate() {
+            if !options[i] {
+                continue;
+            }
+            selected = i;
+            random -= tile.weight;
+            if random < 0.0 {
+                break;
+            }
+        }
+        options.iter_mut().for_each(|o| *o = false);
+        options[selected] = true;
+
+        // propagate constraints
+        let mut stack = vec![(x, y)];
+        while let Some((x, y)) = stack.pop() {
+            for (direction, (x_mod, y_mod)) in WFC_DIRECTIONS.iter().enumerate() {
+                let (next_x, next_y) = (x as i32 + x_mod, y as i32 + y_mod);
+                if next_x < 0
+                    || WFC_GRID_SIZE as i32 <= next_x
+                    || next_y < 0
+                    || WFC_GRID_SIZE as i32 <= next_y
+                {
+                    continue;
+                }
+                let (next_x, next_y) = (next_x as usize, next_y as usize);
+
+                let mut changed = false;
+                for b in 0..tiles.len() {
+                    if !wave[next_y][next_x][b] {
+                        continue;
+                    }
+                    let allowed = wave[y][x]
+                        .iter()
+                        .enumerate()
+                        .any(|(a, o)| *o && compatible[direction][a][b]);
+                    if !allowed {
+                        wave[next_y][next_x][b] = false;
+                        changed = true;
+                    }
+                }
+
+                if changed {
+                    if wave[next_y][next_x].iter().all(|o| !*o) {
+                        return None;
+                    }
+                    stack.push((next_x, next_y));
+                }
+            }
+        }
+    }
+
+    let mut result = [[0; WFC_GRID_SIZE]; WFC_GRID_SIZE];
+    for (y, row) in wave.iter().enumerate() {
+        for (x, options) in row.iter().enumerate() {
+            result[y][x] = options.iter().position(|o| *o)?;
+        }
+    }
+    Some(result)
+}
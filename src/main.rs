@@ -12,14 +12,18 @@ use bevy_rapier3d::prelude::*;
 
 mod animation;
 mod damage;
+mod difficulty;
 mod enemies;
 mod hud;
 mod level;
+mod pickups;
 mod player;
+mod run;
 mod ui;
 mod utils;
 mod weapons;
 
+use level::generation::RoomGeneratorType;
 use utils::IntoState;
 
 const GAME_NAME: &str = "Fridges must die";
@@ -61,13 +65,19 @@ fn main() {
         LogDiagnosticsPlugin::default(),
         RapierPhysicsPlugin::<NoUserData>::default(),
         AudioPlugin,
+    ));
+
+    app.add_plugins((
         animation::AnimationPlugin,
         damage::DamagePlugin,
+        difficulty::DifficultyPlugin,
         enemies::EnemiesPlugin,
         hud::HudPlugin,
         level::LevelPlugin,
+        pickups::PickupsPlugin,
         ui::UiPlugin,
         player::PlayerPlugin,
+        run::RunPlugin,
         weapons::WeaponsPlugin,
     ));
 
@@ -86,6 +96,7 @@ fn main() {
         window_mode: WindowMode::Windowed,
         volume: INITIAL_VOLUME,
         camera_sensitivity: INITIAL_CAMERA_SENSE,
+        room_generator: RoomGeneratorType::Strips,
     });
 
     app.add_systems(Startup, setup_audio_volume);
@@ -137,6 +148,7 @@ pub enum GlobalState {
     Paused,
     GameOver,
     GameWon,
+    Editor,
 }
 impl_into_state!(GlobalState);
 
@@ -158,6 +170,8 @@ struct GameSettings {
     window_mode: WindowMode,
     volume: f32,
     camera_sensitivity: f32,
+    // Used for all levels of the next run
+    room_generator: RoomGeneratorType,
 }
 
 fn setup_audio_volume(audio: Res<Audio>) {
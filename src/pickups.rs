@@ -0,0 +1,286 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    damage::Health,
+    difficulty::RoomDifficulty,
+    player::{player_max_health, Player, PlayerWeapon},
+    weapons::{floating::FloatingObjectBundle, Ammo, Weapon, WeaponAttackTimer},
+    GlobalState,
+};
+
+// Fraction of the full ammo of the player weapon
+const PICKUP_AMMO: f32 = 0.5;
+const PICKUP_HEALTH: i32 = 50;
+// Player weapon shoots this many times faster while power up lasts
+const PICKUP_POWER_UP_FIRE_RATE: f32 = 2.0;
+const PICKUP_POWER_UP_TIME: f32 = 10.0;
+const PICKUP_SIZE: f32 = 1.0;
+
+pub struct PickupsPlugin;
+
+impl Plugin for PickupsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnTransition {
+                from: GlobalState::AssetLoading,
+                to: GlobalState::MainMenu,
+            },
+            init_resources,
+        );
+
+        app.add_systems(
+            Update,
+            (pickup_collect, power_up_update).run_if(in_state(GlobalState::InGame)),
+        );
+    }
+}
+
+#[derive(Resource)]
+pub struct PickupResources {
+    mesh: Handle<Mesh>,
+    ammo_material: Handle<StandardMaterial>,
+    health_material: Handle<StandardMaterial>,
+    power_up_material: Handle<StandardMaterial>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickupType {
+    Ammo,
+    Health,
+    PowerUp,
+}
+
+#[derive(Component)]
+pub struct Pickup {
+    pickup_type: PickupType,
+}
+
+// Makes player weapon shoot faster
+#[derive(Component)]
+pub struct PlayerPowerUp {
+    timer: Timer,
+}
+
+fn init_resources(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let mesh = meshes.add(shape::Cube::new(PICKUP_SIZE).into());
+    let mut pickup_material = |color: Color| {
+        materials.add(StandardMaterial {
+            base_color: color,
+            emissive: color,
+            ..default()
+        })
+    };
+
+    commands.insert_resource(PickupResources {
+        mesh,
+        ammo_material: pickup_material(Color::GOLD),
+        health_material: pickup_material(Color::CRIMSON),
+        power_up_material: pickup_material(Color::CYAN),
+    });
+}
+
+pub fn spawn_pickup(
+    pickup_resources: &PickupResources,
+    pickup_type: PickupType,
+    translation: Vec3,
+    commands: &mut Commands,
+) {
+    let material = match pickup_type {
+        PickupType::Ammo => pickup_resources.ammo_material.clone(),
+        PickupType::Health => pickup_resources.health_material.clone(),
+        PickupType::PowerUp => pickup_resources.power_up_material.clone(),
+    };
+    commands
+        .spawn((
+            FloatingObjectBundle::new(translation),
+            Pickup { pickup_type },
+        ))
+        .with_children(|builder| {
+            builder.spawn(PbrBundle {
+                mesh: pickup_resources.mesh.clone(),
+                material,
+                transform: Transform::from_rotation(Quat::from_rotation_x(
+                    std::f32::consts::FRAC_PI_4,
+                )),
+                ..default()
+            });
+        });
+}
+
+// Weapons from earlier rooms can have more than `max_ammo`,
+// pickups do not take it away
+fn refill_ammo(ammo: u32, max_ammo: u32) -> u32 {
+    (ammo + (max_ammo as f32 * PICKUP_AMMO) as u32)
+        .min(max_ammo)
+        .max(ammo)
+}
+
+fn heal(health: i32, max_health: i32) -> i32 {
+    (health + PICKUP_HEALTH).min(max_health).max(health)
+}
+
+fn pickup_collect(
+    room_difficulty: RoomDifficulty,
+    pickups: Query<&Pickup>,
+    mut player: Query<(Entity, &mut Health), With<Player>>,
+    mut player_weapon: Query<(&Weapon, &mut Ammo), With<PlayerWeapon>>,
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+) {
+    let Ok((player, mut player_health)) = player.get_single_mut() else {
+        return;
+    };
+
+    for collision_event in collision_events.read() {
+        let CollisionEvent::Started(collider_1, collider_2, _) = collision_event else {
+            continue;
+        };
+        let pickup_entity = if *collider_1 == player {
+            *collider_2
+        } else if *collider_2 == player {
+            *collider_1
+        } else {
+            continue;
+        };
+        let Ok(pickup) = pickups.get(pickup_entity) else {
+            continue;
+        };
+
+        match pickup.pickup_type {
+            PickupType::Ammo => {
+                // ammo stays on the ground until player has a weapon
+                let Ok((weapon, mut ammo)) = player_weapon.get_single_mut() else {
+                    continue;
+                };
+                let max_ammo = weapon.weapon_type().max_ammo(room_difficulty.step().ammo);
+                ammo.ammo = refill_ammo(ammo.ammo, max_ammo);
+            }
+            PickupType::Health => {
+                player_health.health = heal(
+                    player_health.health,
+                    player_max_health(*room_difficulty.difficulty),
+                );
+            }
+            PickupType::PowerUp => {
+                commands.entity(player).insert(PlayerPowerUp {
+                    timer: Timer::from_seconds(PICKUP_POWER_UP_TIME, TimerMode::Once),
+                });
+            }
+        }
+
+        if let Some(pickup_commands) = commands.get_entity(pickup_entity) {
+            pickup_commands.despawn_recursive();
+        }
+    }
+}
+
+fn power_up_update(
+    time: Res<Time>,
+    mut player: Query<(Entity, &mut PlayerPowerUp)>,
+    mut player_weapon: Query<&mut WeaponAttackTimer, With<PlayerWeapon>>,
+    mut commands: Commands,
+) {
+    let Ok((player, mut power_up)) = player.get_single_mut() else {
+        return;
+    };
+
+    power_up.timer.tick(time.delta());
+    if power_up.timer.finished() {
+        commands.entity(player).remove::<PlayerPowerUp>();
+        return;
+    }
+
+    // weapon timer is already ticked once by the weapon itself
+    let Ok(mut weapon_attack_timer) = player_weapon.get_single_mut() else {
+        return;
+    };
+    if !weapon_attack_timer.ready {
+        weapon_attack_timer
+            .attack_timer
+            .tick(time.delta().mul_f32(PICKUP_POWER_UP_FIRE_RATE - 1.0));
+        if weapon_attack_timer.attack_timer.finished() {
+            weapon_attack_timer.ready = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        difficulty::Difficulty,
+        weapons::{update_attack_timers, WeaponType},
+    };
+
+    use super::*;
+
+    #[test]
+    fn ammo_is_capped() {
+        let max_ammo = 100;
+        assert_eq!(refill_ammo(0, max_ammo), 50);
+        assert_eq!(refill_ammo(80, max_ammo), max_ammo);
+        assert_eq!(refill_ammo(120, max_ammo), 120);
+    }
+
+    #[test]
+    fn health_is_capped() {
+        for difficulty in [
+            Difficulty::Easy,
+            Difficulty::Normal,
+            Difficulty::Hard,
+            Difficulty::Nightmare,
+        ] {
+            let max_health = player_max_health(difficulty);
+            assert_eq!(heal(1, max_health), 1 + PICKUP_HEALTH);
+            assert_eq!(heal(max_health - 1, max_health), max_health);
+        }
+    }
+
+    // Returns seconds it takes the pistol to get ready
+    fn time_to_ready(power_up: bool) -> f32 {
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default());
+        app.add_systems(Update, (update_attack_timers, power_up_update).chain());
+
+        if power_up {
+            app.world.spawn(PlayerPowerUp {
+                timer: Timer::from_seconds(PICKUP_POWER_UP_TIME, TimerMode::Once),
+            });
+        }
+        let weapon = app
+            .world
+            .spawn((
+                PlayerWeapon {
+                    default_translation: Vec3::ZERO,
+                    bounce_continue: false,
+                    bounce_progress: 0.0,
+                    bounce_speed: 0.0,
+                    bounce_amplitude: 0.0,
+                },
+                WeaponAttackTimer::new(WeaponType::Pistol.attack_speed()),
+            ))
+            .id();
+
+        let step = Duration::from_millis(1);
+        let mut elapsed = Duration::ZERO;
+        while !app.world.get::<WeaponAttackTimer>(weapon).unwrap().ready {
+            app.world.resource_mut::<Time>().advance_by(step);
+            app.update();
+            elapsed += step;
+        }
+        elapsed.as_secs_f32()
+    }
+
+    #[test]
+    fn power_up_makes_weapon_ready_faster() {
+        let attack_speed = WeaponType::Pistol.attack_speed();
+        assert!((time_to_ready(false) - attack_speed).abs() < 0.002);
+        assert!((time_to_ready(true) - attack_speed / PICKUP_POWER_UP_FIRE_RATE).abs() < 0.002);
+    }
+}
@@ -5,7 +5,9 @@ use bevy_rapier3d::{prelude::*, rapier::geometry::CollisionEventFlags};
 
 use crate::{
     animation::Animation,
-    damage::{Damage, Health, KillEvent},
+    damage::{Damage, DamageKind, Health, KillEvent},
+    difficulty::Difficulty,
+    pickups::Pickup,
     ui::UiResources,
     weapons::{floating::FloatingObject, Ammo, ShootEvent, WeaponAttackTimer},
     GameSettings, GlobalState, COLLISION_GROUP_ENEMY, COLLISION_GROUP_LEVEL,
@@ -45,6 +47,7 @@ impl Plugin for PlayerPlugin {
         app.add_systems(
             Update,
             (
+                player_apply_difficulty,
                 player_kills_reading,
                 player_trigger_pause,
                 player_shoot,
@@ -142,6 +145,7 @@ impl PlayerThrownWeapon {
             },
             damage: Damage {
                 damage: PLAYER_THROW_DAMAGE,
+                kind: DamageKind::Blunt,
             },
         }
     }
@@ -347,6 +351,21 @@ fn player_toggle_hud_off(hud: Query<Entity, With<PlayerHud>>, mut commands: Comm
     });
 }
 
+pub fn player_max_health(difficulty: Difficulty) -> i32 {
+    (PLAYER_HEALTH as f32 * difficulty.modifiers().player_health) as i32
+}
+
+// Player is spawned in the main menu before difficulty
+// is selected, so health is adjusted once the run starts.
+fn player_apply_difficulty(
+    difficulty: Res<Difficulty>,
+    mut player: Query<&mut Health, Added<Player>>,
+) {
+    for mut health in player.iter_mut() {
+        health.health = player_max_health(*difficulty);
+    }
+}
+
 fn player_kills_reading(
     mut player: Query<Entity, With<Player>>,
     mut kill_events: EventReader<KillEvent>,
@@ -368,6 +387,7 @@ fn player_pick_up_weapon(
     player_camera: Query<Entity, With<PlayerCamera>>,
     player_weapon: Query<Entity, With<PlayerWeapon>>,
     floating_objects: Query<(Entity, &Children), With<FloatingObject>>,
+    pickups: Query<(), With<Pickup>>,
     mut commands: Commands,
     mut collision_events: EventReader<CollisionEvent>,
 ) {
@@ -411,6 +431,11 @@ fn player_pick_up_weapon(
             continue;
         };
 
+        // pickups float too, but `pickup_collect` handles them
+        if pickups.contains(floating_object_entity) {
+            continue;
+        }
+
         let Some(mut floating_object_commands) = commands.get_entity(floating_object_entity) else {
             continue;
         };
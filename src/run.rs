@@ -0,0 +1,200 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{level::LevelInfo, GlobalState};
+
+// If set, every run will use this seed
+const RUN_SEED_ENV: &str = "FRIDGES_SEED";
+// If set, overrides number of rooms before the boss
+const RUN_ROOMS_ENV: &str = "FRIDGES_RUN_ROOMS";
+
+// Combat rng stream is seeded with the run seed mixed with this,
+// so it does not repeat the level generation rng
+const RUN_COMBAT_SEED_SALT: u64 = 0x9e37_79b9_7f4a_7c15;
+
+const RUN_DEFAULT_ROOMS: i32 = 10;
+const RUN_DEFAULT_ENDLESS_BOSS_EVERY: i32 = 10;
+
+pub struct RunPlugin;
+
+impl Plugin for RunPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RunSeed::from_env());
+        app.insert_resource(RunConfig::from_env());
+        app.init_resource::<RunMode>();
+        app.init_resource::<EndlessScore>();
+
+        app.add_systems(
+            OnTransition {
+                from: GlobalState::InGame,
+                to: GlobalState::GameOver,
+            },
+            record_endless_score,
+        );
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+pub enum RunMode {
+    // Run ends after the boss is defeated
+    #[default]
+    Normal,
+    // Run only ends when player dies
+    Endless,
+}
+
+impl RunMode {
+    pub fn next(self) -> Self {
+        match self {
+            RunMode::Normal => RunMode::Endless,
+            RunMode::Endless => RunMode::Normal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressDisplay {
+    Percent,
+    Rooms,
+}
+
+// Describes the shape of the run. Rooms are indexed
+// from 0, tutorial room is before the first one.
+// Boss room comes right after the last normal room.
+#[derive(Resource)]
+pub struct RunConfig {
+    // Number of normal rooms before the boss room
+    pub rooms: i32,
+    // In endless mode every N-th room is a boss room
+    pub endless_boss_every: i32,
+    pub progress_display: ProgressDisplay,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        Self {
+            rooms: RUN_DEFAULT_ROOMS,
+            endless_boss_every: RUN_DEFAULT_ENDLESS_BOSS_EVERY,
+            progress_display: ProgressDisplay::Percent,
+        }
+    }
+}
+
+impl RunConfig {
+    pub const TUTORIAL_ROOM: i32 = -1;
+
+    fn from_env() -> Self {
+        let Ok(value) = std::env::var(RUN_ROOMS_ENV) else {
+            return Self::default();
+        };
+        match value.parse::<i32>() {
+            Ok(rooms) if 0 < rooms => Self { rooms, ..default() },
+            _ => {
+                warn!("Invalid {RUN_ROOMS_ENV} value: {value}. Using default run length.");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn is_boss_room(&self, run_mode: RunMode, room: i32) -> bool {
+        match run_mode {
+            RunMode::Normal => room == self.rooms,
+            RunMode::Endless => 0 < room && room % self.endless_boss_every == 0,
+        }
+    }
+
+    pub fn is_pre_boss_room(&self, run_mode: RunMode, room: i32) -> bool {
+        self.is_boss_room(run_mode, room + 1)
+    }
+
+    // Normal runs are won after the boss room is cleared.
+    // `room` is the index of the next room.
+    pub fn is_won(&self, run_mode: RunMode, room: i32) -> bool {
+        run_mode == RunMode::Normal && self.rooms < room
+    }
+
+    // Fraction of the normal run. Goes above 1.0 in
+    // endless runs.
+    pub fn progress(&self, room: i32) -> f32 {
+        room as f32 / self.rooms as f32
+    }
+
+    pub fn progress_text(&self, run_mode: RunMode, room: i32) -> String {
+        match (run_mode, self.progress_display) {
+            (RunMode::Endless, _) => format!("{}", room.max(0)),
+            (RunMode::Normal, ProgressDisplay::Percent) => {
+                format!("{}%", room * 100 / self.rooms)
+            }
+            (RunMode::Normal, ProgressDisplay::Rooms) => {
+                format!("{}/{}", room.max(0), self.rooms)
+            }
+        }
+    }
+}
+
+// Number of rooms cleared in endless runs
+#[derive(Default, Resource)]
+pub struct EndlessScore {
+    pub last: u32,
+    pub best: u32,
+}
+
+#[derive(Resource)]
+pub struct RunSeed {
+    pub seed: u64,
+    pub fixed: bool,
+}
+
+impl RunSeed {
+    fn from_env() -> Self {
+        match std::env::var(RUN_SEED_ENV).ok().map(|s| s.parse::<u64>()) {
+            Some(Ok(seed)) => Self { seed, fixed: true },
+            Some(Err(e)) => {
+                warn!("Invalid {RUN_SEED_ENV} value: {e}. Using random seeds.");
+                Self {
+                    seed: 0,
+                    fixed: false,
+                }
+            }
+            None => Self {
+                seed: 0,
+                fixed: false,
+            },
+        }
+    }
+
+    /// Creates rng for a new run. If seed is not fixed
+    /// a new random one is picked for every run.
+    pub fn new_run(&mut self) -> RunRng {
+        if !self.fixed {
+            self.seed = rand::random();
+        }
+        info!("Run seed: {}", self.seed);
+        RunRng(StdRng::seed_from_u64(self.seed))
+    }
+
+    /// Creates combat rng for the run started by `new_run`.
+    pub fn combat_rng(&self) -> CombatRng {
+        CombatRng(StdRng::seed_from_u64(self.seed ^ RUN_COMBAT_SEED_SALT))
+    }
+}
+
+// All random decisions about rooms made during
+// the run must use this rng, so runs could be
+// replayed with the same seed.
+#[derive(Resource)]
+pub struct RunRng(pub StdRng);
+
+// Random decisions made during fights. How much they
+// draw depends on how the player fights, so they must
+// not touch `RunRng` and change the next rooms.
+#[derive(Resource)]
+pub struct CombatRng(pub StdRng);
+
+fn record_endless_score(level_info: Res<LevelInfo>, mut endless_score: ResMut<EndlessScore>) {
+    if level_info.run_mode != RunMode::Endless {
+        return;
+    }
+    endless_score.last = level_info.room.max(0) as u32;
+    endless_score.best = endless_score.best.max(endless_score.last);
+}
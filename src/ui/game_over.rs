@@ -1,6 +1,12 @@
 use bevy::prelude::*;
 
-use crate::{utils::remove_all_with, GlobalState, UiState};
+use crate::{
+    difficulty::Difficulty,
+    level::LevelInfo,
+    run::{EndlessScore, RunMode},
+    utils::remove_all_with,
+    GlobalState, UiState,
+};
 
 use super::{spawn_button, ButtonText, UiConfig};
 
@@ -23,7 +29,13 @@ enum GameOverMenuButton {
     MainMenu,
 }
 
-fn setup_pause_menu(mut commands: Commands, config: Res<UiConfig>) {
+fn setup_pause_menu(
+    mut commands: Commands,
+    config: Res<UiConfig>,
+    difficulty: Res<Difficulty>,
+    level_info: Res<LevelInfo>,
+    endless_score: Res<EndlessScore>,
+) {
     commands
         .spawn((
             NodeBundle {
@@ -42,6 +54,30 @@ fn setup_pause_menu(mut commands: Commands, config: Res<UiConfig>) {
                 .with_style(config.title_style.clone()),
             );
 
+            builder.spawn(
+                (TextBundle {
+                    text: Text::from_section(
+                        format!("Difficulty: {:?}", *difficulty),
+                        config.options_text_style.clone(),
+                    ),
+                    ..default()
+                })
+                .with_style(config.title_style.clone()),
+            );
+
+            if level_info.run_mode == RunMode::Endless {
+                builder.spawn(
+                    (TextBundle {
+                        text: Text::from_section(
+                            format!("Rooms: {} Best: {}", endless_score.last, endless_score.best),
+                            config.options_text_style.clone(),
+                        ),
+                        ..default()
+                    })
+                    .with_style(config.title_style.clone()),
+                );
+            }
+
             // Buttons
             builder
                 .spawn((NodeBundle {
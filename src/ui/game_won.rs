@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::{utils::remove_all_with, GlobalState, UiState};
+use crate::{difficulty::Difficulty, utils::remove_all_with, GlobalState, UiState};
 
 use super::{spawn_button, ButtonText, UiConfig};
 
@@ -25,7 +25,7 @@ enum GameWonMenuButton {
     MainMenu,
 }
 
-fn setup_pause_menu(mut commands: Commands, config: Res<UiConfig>) {
+fn setup_pause_menu(mut commands: Commands, config: Res<UiConfig>, difficulty: Res<Difficulty>) {
     commands
         .spawn((
             NodeBundle {
@@ -45,6 +45,17 @@ fn setup_pause_menu(mut commands: Commands, config: Res<UiConfig>) {
                 .with_style(config.title_style.clone()),
             );
 
+            builder.spawn(
+                (TextBundle {
+                    text: Text::from_section(
+                        format!("Difficulty: {:?}", *difficulty),
+                        config.options_text_style.clone(),
+                    ),
+                    ..default()
+                })
+                .with_style(config.title_style.clone()),
+            );
+
             // Buttons
             builder
                 .spawn((NodeBundle {
@@ -1,6 +1,9 @@
 use bevy::{app::AppExit, prelude::*};
 
-use crate::{utils::remove_all_with, GlobalState, UiState, CREATED_BY, GAME_NAME};
+use crate::{
+    difficulty::Difficulty, run::RunMode, utils::remove_all_with, GlobalState, UiState, CREATED_BY,
+    GAME_NAME,
+};
 
 use super::{spawn_button, ButtonText, UiConfig};
 
@@ -9,7 +12,11 @@ pub struct MainMenuPlugin;
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(UiState::MainMenu), setup_main_menu);
-        app.add_systems(Update, button_system.run_if(in_state(UiState::MainMenu)));
+        app.add_systems(
+            Update,
+            (button_system, update_difficulty_text, update_run_mode_text)
+                .run_if(in_state(UiState::MainMenu)),
+        );
         app.add_systems(OnExit(UiState::MainMenu), remove_all_with::<MainMenu>);
     }
 }
@@ -17,9 +24,18 @@ impl Plugin for MainMenuPlugin {
 #[derive(Component)]
 struct MainMenu;
 
+#[derive(Component)]
+struct MainMenuDifficultyText;
+
+#[derive(Component)]
+struct MainMenuRunModeText;
+
 #[derive(Debug, Clone, Copy, Component)]
 enum MainMenuButton {
     Play,
+    Difficulty,
+    Mode,
+    Editor,
     Options,
     Quit,
 }
@@ -53,6 +69,25 @@ fn setup_main_menu(mut commands: Commands, config: Res<UiConfig>) {
                 },))
                 .with_children(|builder| {
                     spawn_button(builder, &config, MainMenuButton::Play);
+                    spawn_button(builder, &config, MainMenuButton::Difficulty);
+                    builder.spawn((
+                        TextBundle {
+                            text: Text::from_section("", config.options_text_style.clone()),
+                            ..default()
+                        }
+                        .with_style(config.button_style.clone()),
+                        MainMenuDifficultyText,
+                    ));
+                    spawn_button(builder, &config, MainMenuButton::Mode);
+                    builder.spawn((
+                        TextBundle {
+                            text: Text::from_section("", config.options_text_style.clone()),
+                            ..default()
+                        }
+                        .with_style(config.button_style.clone()),
+                        MainMenuRunModeText,
+                    ));
+                    spawn_button(builder, &config, MainMenuButton::Editor);
                     spawn_button(builder, &config, MainMenuButton::Options);
                     spawn_button(builder, &config, MainMenuButton::Quit);
                 });
@@ -76,6 +111,8 @@ fn button_system(
         (Changed<Interaction>, With<Button>),
     >,
     mut main_menu_texts: Query<&mut Text, With<ButtonText<MainMenuButton>>>,
+    mut difficulty: ResMut<Difficulty>,
+    mut run_mode: ResMut<RunMode>,
     mut main_menu_state: ResMut<NextState<UiState>>,
     mut global_state: ResMut<NextState<GlobalState>>,
     mut exit: EventWriter<AppExit>,
@@ -92,6 +129,15 @@ fn button_system(
                     MainMenuButton::Play => {
                         global_state.set(GlobalState::InGame);
                     }
+                    MainMenuButton::Difficulty => {
+                        *difficulty = difficulty.next();
+                    }
+                    MainMenuButton::Mode => {
+                        *run_mode = run_mode.next();
+                    }
+                    MainMenuButton::Editor => {
+                        global_state.set(GlobalState::Editor);
+                    }
                     MainMenuButton::Options => {
                         main_menu_state.set(UiState::Options);
                     }
@@ -107,3 +153,19 @@ fn button_system(
         }
     }
 }
+
+fn update_difficulty_text(
+    difficulty: Res<Difficulty>,
+    mut difficulty_text: Query<&mut Text, With<MainMenuDifficultyText>>,
+) {
+    let mut text = difficulty_text.single_mut();
+    text.sections[0].value = format!("{:?}", *difficulty);
+}
+
+fn update_run_mode_text(
+    run_mode: Res<RunMode>,
+    mut run_mode_text: Query<&mut Text, With<MainMenuRunModeText>>,
+) {
+    let mut text = run_mode_text.single_mut();
+    text.sections[0].value = format!("{:?}", *run_mode);
+}
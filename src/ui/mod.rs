@@ -57,6 +57,28 @@ impl Plugin for UiPlugin {
             (set_state::<UiState, { UiState::Stats as u8 }>, grab_mouse),
         );
 
+        app.add_systems(
+            OnTransition {
+                from: GlobalState::MainMenu,
+                to: GlobalState::Editor,
+            },
+            set_state::<UiState, { UiState::NoUi as u8 }>,
+        );
+        app.add_systems(
+            OnTransition {
+                from: GlobalState::Editor,
+                to: GlobalState::MainMenu,
+            },
+            set_state::<UiState, { UiState::MainMenu as u8 }>,
+        );
+        app.add_systems(
+            OnTransition {
+                from: GlobalState::Editor,
+                to: GlobalState::InGame,
+            },
+            (set_state::<UiState, { UiState::Stats as u8 }>, grab_mouse),
+        );
+
         app.add_systems(
             OnTransition {
                 from: GlobalState::InGame,
@@ -137,7 +159,6 @@ pub struct UiAssets {
 
 #[derive(Debug, Clone, Resource)]
 pub struct UiConfig {
-    pub clear_background: Color,
     pub panels_background: Color,
     pub button_background: Color,
     pub button_text_color_normal: Color,
@@ -251,7 +272,6 @@ fn init_resources(
 
 fn setup_ui_config(ui_assets: Res<UiAssets>, mut commands: Commands) {
     commands.insert_resource(UiConfig {
-        clear_background: Color::NONE,
         panels_background: Color::NONE, //BLACK,
         button_background: Color::NONE,
 
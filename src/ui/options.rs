@@ -1,7 +1,12 @@
 use bevy::{prelude::*, window::WindowMode};
 use bevy_kira_audio::{Audio, AudioControl};
 
-use crate::{utils::remove_all_with, GameSettings, GlobalState, UiState};
+use crate::{
+    level::generation::RoomGeneratorType,
+    run::{ProgressDisplay, RunConfig},
+    utils::remove_all_with,
+    GameSettings, GlobalState, UiState,
+};
 
 use super::{spawn_button, ButtonText, UiConfig};
 
@@ -17,6 +22,8 @@ impl Plugin for OptionsPlugin {
                 update_window_mode_text,
                 update_volume_value_text,
                 update_camera_sense_value_text,
+                update_generator_text,
+                update_progress_display_text,
             )
                 .run_if(in_state(UiState::Options)),
         );
@@ -35,6 +42,8 @@ enum OptionMenuButton {
     VolumeDown,
     SenseUp,
     SenseDown,
+    Generator,
+    Progress,
     Back,
 }
 
@@ -47,6 +56,12 @@ struct OptionsVolumeText;
 #[derive(Component)]
 struct OptionsCameraSenseText;
 
+#[derive(Component)]
+struct OptionsGeneratorText;
+
+#[derive(Component)]
+struct OptionsProgressText;
+
 fn setup_option_menu(mut commands: Commands, config: Res<UiConfig>) {
     commands
         .spawn((
@@ -58,7 +73,7 @@ fn setup_option_menu(mut commands: Commands, config: Res<UiConfig>) {
             OptionsMenu,
         ))
         .with_children(|builder| {
-            // 2 rows of settings
+            // rows of settings
             // and 1 Back button
             builder
                 .spawn((NodeBundle {
@@ -128,6 +143,44 @@ fn setup_option_menu(mut commands: Commands, config: Res<UiConfig>) {
                             ));
                         });
 
+                    // Room generator
+                    builder
+                        .spawn((NodeBundle {
+                            style: config.options_buttons_area_style.clone(),
+                            background_color: config.panels_background.into(),
+                            ..default()
+                        },))
+                        .with_children(|builder| {
+                            spawn_button(builder, &config, OptionMenuButton::Generator);
+                            builder.spawn((
+                                TextBundle {
+                                    text: Text::from_section("", config.options_text_style.clone()),
+                                    ..default()
+                                }
+                                .with_style(config.button_style.clone()),
+                                OptionsGeneratorText,
+                            ));
+                        });
+
+                    // Progress display
+                    builder
+                        .spawn((NodeBundle {
+                            style: config.options_buttons_area_style.clone(),
+                            background_color: config.panels_background.into(),
+                            ..default()
+                        },))
+                        .with_children(|builder| {
+                            spawn_button(builder, &config, OptionMenuButton::Progress);
+                            builder.spawn((
+                                TextBundle {
+                                    text: Text::from_section("", config.options_text_style.clone()),
+                                    ..default()
+                                }
+                                .with_style(config.button_style.clone()),
+                                OptionsProgressText,
+                            ));
+                        });
+
                     spawn_button(builder, &config, OptionMenuButton::Back);
                 });
         });
@@ -145,6 +198,7 @@ fn button_system(
     // audio: ResMut<Audio>,
     mut windows: Query<&mut Window>,
     mut game_settings: ResMut<GameSettings>,
+    mut run_config: ResMut<RunConfig>,
     mut texts: Query<&mut Text, With<ButtonText<OptionMenuButton>>>,
     mut ui_state: ResMut<NextState<UiState>>,
 ) {
@@ -185,6 +239,18 @@ fn button_system(
                             game_settings.camera_sensitivity = 0.0;
                         }
                     }
+                    OptionMenuButton::Generator => {
+                        game_settings.room_generator = match game_settings.room_generator {
+                            RoomGeneratorType::Strips => RoomGeneratorType::Wfc,
+                            RoomGeneratorType::Wfc => RoomGeneratorType::Strips,
+                        };
+                    }
+                    OptionMenuButton::Progress => {
+                        run_config.progress_display = match run_config.progress_display {
+                            ProgressDisplay::Percent => ProgressDisplay::Rooms,
+                            ProgressDisplay::Rooms => ProgressDisplay::Percent,
+                        };
+                    }
                     OptionMenuButton::Back => match global_state.get() {
                         GlobalState::MainMenu => ui_state.set(UiState::MainMenu),
                         GlobalState::Paused => ui_state.set(UiState::Paused),
@@ -225,3 +291,19 @@ fn update_camera_sense_value_text(
     let mut text = volume_text.single_mut();
     text.sections[0].value = format!("{:.2}", game_settings.camera_sensitivity);
 }
+
+fn update_generator_text(
+    game_settings: Res<GameSettings>,
+    mut generator_text: Query<&mut Text, With<OptionsGeneratorText>>,
+) {
+    let mut text = generator_text.single_mut();
+    text.sections[0].value = format!("{:?}", game_settings.room_generator);
+}
+
+fn update_progress_display_text(
+    run_config: Res<RunConfig>,
+    mut progress_text: Query<&mut Text, With<OptionsProgressText>>,
+) {
+    let mut text = progress_text.single_mut();
+    text.sections[0].value = format!("{:?}", run_config.progress_display);
+}
@@ -4,6 +4,7 @@ use crate::{
     damage::Health,
     level::LevelInfo,
     player::{Player, PlayerWeapon},
+    run::RunConfig,
     utils::remove_all_with,
     weapons::Ammo,
     UiState,
@@ -128,7 +129,7 @@ fn update_player_ammo(
     let mut text = window_mode_text.single_mut();
     match player_ammo.get_single() {
         Ok(ammo) => text.sections[0].value = format!("{}", ammo.ammo),
-        Err(_) => text.sections[0].value = format!("---"),
+        Err(_) => text.sections[0].value = "---".to_string(),
     }
 }
 
@@ -145,8 +146,9 @@ fn update_plyaer_hp(
 
 fn update_game_progress(
     level_info: Res<LevelInfo>,
+    run_config: Res<RunConfig>,
     mut volume_text: Query<&mut Text, With<StatsGameProgress>>,
 ) {
     let mut text = volume_text.single_mut();
-    text.sections[0].value = format!("{}%", level_info.game_progress);
+    text.sections[0].value = run_config.progress_text(level_info.run_mode, level_info.room);
 }
@@ -2,10 +2,14 @@ use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 use bevy_kira_audio::{Audio, AudioControl, AudioSource};
 use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
 
 use crate::{
-    animation::Animation, damage::Damage, level::LevelObject, GlobalState, COLLISION_GROUP_ENEMY,
-    COLLISION_GROUP_LEVEL, COLLISION_GROUP_PLAYER, COLLISION_GROUP_PROJECTILES,
+    animation::Animation,
+    damage::{Damage, DamageKind},
+    level::LevelObject,
+    GlobalState, COLLISION_GROUP_ENEMY, COLLISION_GROUP_LEVEL, COLLISION_GROUP_PLAYER,
+    COLLISION_GROUP_PROJECTILES,
 };
 
 use self::floating::{FloatingObjectBundle, FloatingObjectInternal};
@@ -110,7 +114,7 @@ pub struct WeaponAssets {
     pub round_scene: Handle<Scene>,
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
 pub enum WeaponType {
     #[default]
     Pistol,
@@ -118,11 +122,49 @@ pub enum WeaponType {
     Minigun,
 }
 
+impl WeaponType {
+    pub fn ammo(self) -> u32 {
+        match self {
+            WeaponType::Pistol => PISTOL_AMMO,
+            WeaponType::Shotgun => SHOTGUN_AMMO,
+            WeaponType::Minigun => MINIGUN_AMMO,
+        }
+    }
+
+    // Ammo of spawned weapons and the most ammo
+    // pickups can fill the weapon up to
+    pub fn max_ammo(self, ammo_multiplier: f32) -> u32 {
+        (self.ammo() as f32 * ammo_multiplier) as u32
+    }
+
+    pub fn attack_speed(self) -> f32 {
+        match self {
+            WeaponType::Pistol => PISTOL_ATTACK_SPEED,
+            WeaponType::Shotgun => SHOTGUN_ATTACK_SPEED,
+            WeaponType::Minigun => MINIGUN_ATTACK_SPEED,
+        }
+    }
+
+    pub fn projectile_velocity(self) -> f32 {
+        match self {
+            WeaponType::Pistol => PISTOL_PROJECTILE_VELOCITY,
+            WeaponType::Shotgun => SHOTGUN_PROJECTILE_VELOCITY,
+            WeaponType::Minigun => MINIGUN_PROJECTILE_VELOCITY,
+        }
+    }
+}
+
 #[derive(Default, Component)]
 pub struct Weapon {
     weapon_type: WeaponType,
 }
 
+impl Weapon {
+    pub fn weapon_type(&self) -> WeaponType {
+        self.weapon_type
+    }
+}
+
 #[derive(Component)]
 pub struct WeaponModel;
 
@@ -218,6 +260,8 @@ impl Default for WeaponBundle {
 #[derive(Default, Component)]
 pub struct Projectile {
     pub direction: Vec3,
+    // Weapon that fired the projectile
+    pub weapon: Option<Entity>,
 }
 
 #[derive(Bundle)]
@@ -302,9 +346,13 @@ pub(crate) use attach_weapon;
 pub fn spawn_weapon(
     weapon_assets: &WeaponAssets,
     weapon_type: WeaponType,
+    ammo_multiplier: f32,
     commands: &mut Commands,
     transform: Transform,
 ) {
+    let ammo = Ammo {
+        ammo: weapon_type.max_ammo(ammo_multiplier),
+    };
     match weapon_type {
         WeaponType::Pistol => {
             commands
@@ -312,7 +360,7 @@ pub fn spawn_weapon(
                 .with_children(|builder| {
                     let transform = Transform::default();
                     _ = attach_weapon!(builder, weapon_assets, transform, pistol, pistol_scene)
-                        .insert(FloatingObjectInternal);
+                        .insert((FloatingObjectInternal, ammo));
                 });
         }
 
@@ -322,7 +370,7 @@ pub fn spawn_weapon(
                 .with_children(|builder| {
                     let transform = Transform::default();
                     _ = attach_weapon!(builder, weapon_assets, transform, shotgun, shotgun_scene)
-                        .insert(FloatingObjectInternal);
+                        .insert((FloatingObjectInternal, ammo));
                 });
         }
         WeaponType::Minigun => {
@@ -331,13 +379,13 @@ pub fn spawn_weapon(
                 .with_children(|builder| {
                     let transform = Transform::default();
                     _ = attach_weapon!(builder, weapon_assets, transform, minigun, minigun_scene)
-                        .insert(FloatingObjectInternal);
+                        .insert((FloatingObjectInternal, ammo));
                 });
         }
     }
 }
 
-fn update_attack_timers(time: Res<Time>, mut timers: Query<&mut WeaponAttackTimer>) {
+pub(crate) fn update_attack_timers(time: Res<Time>, mut timers: Query<&mut WeaponAttackTimer>) {
     for mut timer in timers.iter_mut() {
         if !timer.ready {
             timer.attack_timer.tick(time.delta());
@@ -421,9 +469,11 @@ fn pistol_shoot(
         },
         damage: Damage {
             damage: PISTOL_DAMAGE,
+            kind: DamageKind::Ballistic,
         },
         projectile: Projectile {
             direction: event.direction,
+            weapon: Some(event.weapon_entity),
         },
         ..default()
     });
@@ -518,9 +568,11 @@ fn shotgun_shoot(
                 },
                 damage: Damage {
                     damage: SHOTGUN_DAMAGE,
+                    kind: DamageKind::Pellet,
                 },
                 projectile: Projectile {
                     direction: event.direction,
+                    weapon: Some(event.weapon_entity),
                 },
                 ..default()
             });
@@ -613,9 +665,11 @@ fn minigun_shoot(
             },
             damage: Damage {
                 damage: MINIGUN_DAMAGE,
+                kind: DamageKind::Ballistic,
             },
             projectile: Projectile {
                 direction: event.direction,
+                weapon: Some(event.weapon_entity),
             },
             ..default()
         });